@@ -6,12 +6,6 @@ echo "Welcome to this fantastic build process!"
 echo "Cleaning /volume/bin folder..."
 rm -rf /volume/bin/*
 
-# Apply appropriate patches without further delay
-echo "Applying patches..."
-patch -p1 raptorq/src/decoder.rs decoder.rs.patch
-
-echo "Patching complete."
-
 # Set default output directory if not provided
 if [ -z "$OUTPUT_DIR" ]; then
     OUTPUT_DIR="./bin"
@@ -88,6 +82,15 @@ get_exe_name() {
     fi
 }
 
+get_addon_lib_name() {
+    local target="$1"
+    if [[ "$target" == *"windows"* ]]; then
+        echo "raptorq_node.dll"
+    else
+        echo "libraptorq_node.so"
+    fi
+}
+
 echo "Starting Rust cross-compilation..."
 
 for rust_target in "${RUST_TARGETS[@]}"; do
@@ -124,8 +127,26 @@ for rust_target in "${RUST_TARGETS[@]}"; do
     if [[ "$rust_target" != *"windows"* ]]; then
         chmod +x "$OUTPUT_PATH"
     fi
+
+    # Build the Node-API addon for the same target
+    ADDON_PATH="${TARGET_DIR}/raptorq.node"
+    echo "Building Node-API addon for ${rust_target} to ${ADDON_PATH}..."
+
+    cargo build --release --target "$rust_target" --manifest-path raptorq-node/Cargo.toml
+
+    if [ $? -ne 0 ]; then
+        echo "Error: Node-API addon build failed for ${rust_target}"
+        exit 1
+    fi
+
+    cp "raptorq-node/target/${rust_target}/release/$(get_addon_lib_name "$rust_target")" "$ADDON_PATH"
+
+    if [ $? -ne 0 ]; then
+        echo "Error: Failed to copy Node-API addon for ${rust_target}"
+        exit 1
+    fi
     
     echo "Successfully built ${rust_target}"
 done
 
-echo "All required Rust binaries built successfully!"
+echo "All required Rust binaries and addons built successfully!"
@@ -0,0 +1,283 @@
+//! Node-API bindings for the vendored `raptorq` crate
+//!
+//! This exposes the same encode/decode semantics as the `raptorq` CLI, but in-process, so no
+//! process is spawned per object and nothing is shuffled through pipes. Input `Uint8Array`s are
+//! borrowed from JavaScript for the duration of a call; encoders keep their own copy of the data,
+//! since they outlive the call. Every packet or block is allocated once and handed to JavaScript
+//! as a `Buffer` without being copied again.
+//!
+//! Method names are kept in snake_case to match the rest of the JavaScript interface.
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use raptorq::{EncodingPacket, ObjectTransmissionInformation};
+
+const OTI_SIZE: usize = 12;
+
+/// Encoding parameters, mirroring the encoding flags of the CLI.
+#[napi(object)]
+pub struct EncodeOptions {
+    #[napi(js_name = "symbol_size")]
+    pub symbol_size: u32,
+    #[napi(js_name = "num_source_blocks")]
+    pub num_source_blocks: u32,
+    #[napi(js_name = "num_sub_blocks")]
+    pub num_sub_blocks: u32,
+    #[napi(js_name = "symbol_alignment")]
+    pub symbol_alignment: u32,
+}
+
+/// A source block that has just been fully decoded.
+#[napi(object)]
+pub struct DecodedBlock {
+    pub sbn: u32,
+    /// Position of the block's first byte within the object
+    pub offset: i64,
+    pub data: Buffer,
+}
+
+fn invalid_arg(message: impl Into<String>) -> Error {
+    Error::new(Status::InvalidArg, message.into())
+}
+
+fn from_raptorq(error: raptorq::Error) -> Error {
+    invalid_arg(error.to_string())
+}
+
+fn narrow<T: TryFrom<u32>>(value: u32, name: &str) -> Result<T> {
+    T::try_from(value).map_err(|_| invalid_arg(format!("{} is out of range: {}", name, value)))
+}
+
+fn parse_oti(oti: &[u8]) -> Result<ObjectTransmissionInformation> {
+    if oti.len() != OTI_SIZE {
+        return Err(invalid_arg(format!("OTI must be a {}-byte Uint8Array", OTI_SIZE)));
+    }
+    ObjectTransmissionInformation::try_deserialize(oti).map_err(from_raptorq)
+}
+
+// A single source block is valid when it could be the only block of an object, so the checks of
+// `ObjectTransmissionInformation::try_new` (at most K'max symbols among them) apply to it
+fn check_block_length(config: &ObjectTransmissionInformation, block_length: u64) -> Result<()> {
+    ObjectTransmissionInformation::try_new(
+        block_length,
+        config.symbol_size(),
+        1,
+        config.sub_blocks(),
+        config.symbol_alignment(),
+    )
+    .map(|_| ())
+    .map_err(from_raptorq)
+}
+
+fn parse_packet(packet: &[u8]) -> Result<EncodingPacket> {
+    EncodingPacket::try_deserialize(packet).map_err(from_raptorq)
+}
+
+// Every packet is serialized straight into the allocation that backs its `Buffer`
+fn packet_buffer(
+    encoder: &raptorq::SourceBlockEncoder,
+    write: impl FnOnce(&mut [u8]) -> usize,
+) -> Buffer {
+    let mut packet = vec![0; 4 + encoder.symbol_size()];
+    let length = write(&mut packet);
+    packet.truncate(length);
+    packet.into()
+}
+
+fn source_buffers(encoder: &raptorq::SourceBlockEncoder) -> Vec<Buffer> {
+    (0..encoder.source_symbol_count())
+        .map(|esi| packet_buffer(encoder, |packet| encoder.write_source_packet(esi, packet)))
+        .collect()
+}
+
+fn repair_buffers(encoder: &raptorq::SourceBlockEncoder, start_repair_symbol_id: u32, packets: u32) -> Vec<Buffer> {
+    (0..packets)
+        .map(|i| {
+            let repair_symbol_id = start_repair_symbol_id + i;
+            packet_buffer(encoder, |packet| encoder.write_repair_packet(repair_symbol_id, packet))
+        })
+        .collect()
+}
+
+#[napi]
+pub struct Encoder {
+    encoder: raptorq::Encoder<'static>,
+}
+
+#[napi]
+impl Encoder {
+    /// Encodes `data`, using its length as the transfer length.
+    #[napi(constructor)]
+    pub fn new(data: Uint8Array, options: EncodeOptions) -> Result<Self> {
+        let symbol_size = narrow(options.symbol_size, "symbol_size")?;
+        let source_blocks = narrow(options.num_source_blocks, "num_source_blocks")?;
+        let sub_blocks = narrow(options.num_sub_blocks, "num_sub_blocks")?;
+        let symbol_alignment = narrow(options.symbol_alignment, "symbol_alignment")?;
+        let config = ObjectTransmissionInformation::try_new(
+            data.len() as u64,
+            symbol_size,
+            source_blocks,
+            sub_blocks,
+            symbol_alignment,
+        ).map_err(from_raptorq)?;
+
+        Ok(Encoder {
+            encoder: raptorq::Encoder::new(&data, config).into_owned(),
+        })
+    }
+
+    /// The 12-byte RFC 6330 OTI header for this object.
+    #[napi(js_name = "oti")]
+    pub fn oti(&self) -> Buffer {
+        self.encoder.get_config().serialize().to_vec().into()
+    }
+
+    /// All source packets followed by `repair_packets_per_block` repair packets, block by block.
+    #[napi(js_name = "encoded_packets")]
+    pub fn encoded_packets(&self, repair_packets_per_block: u32) -> Vec<Buffer> {
+        self.encoder
+            .get_block_encoders()
+            .iter()
+            .flat_map(|encoder| {
+                let mut packets = source_buffers(encoder);
+                packets.extend(repair_buffers(encoder, 0, repair_packets_per_block));
+                packets
+            })
+            .collect()
+    }
+
+    #[napi(js_name = "block_encoders")]
+    pub fn block_encoders(&self) -> Vec<SourceBlockEncoder> {
+        self.encoder
+            .get_block_encoders()
+            .iter()
+            .map(|encoder| SourceBlockEncoder { encoder: encoder.clone() })
+            .collect()
+    }
+}
+
+#[napi]
+pub struct SourceBlockEncoder {
+    encoder: raptorq::SourceBlockEncoder<'static>,
+}
+
+#[napi]
+impl SourceBlockEncoder {
+    /// Encodes a single source block. `data` must be a whole number of symbols.
+    #[napi(constructor)]
+    pub fn new(sbn: u32, oti: Uint8Array, data: Uint8Array) -> Result<Self> {
+        let sbn = narrow(sbn, "sbn")?;
+        let config = parse_oti(&oti)?;
+        if data.len() % config.symbol_size() as usize != 0 {
+            return Err(invalid_arg("Block data must be a non-zero multiple of the symbol size"));
+        }
+        check_block_length(&config, data.len() as u64)?;
+
+        Ok(SourceBlockEncoder {
+            encoder: raptorq::SourceBlockEncoder::new(sbn, &config, &data).into_owned(),
+        })
+    }
+
+    #[napi(js_name = "source_packets")]
+    pub fn source_packets(&self) -> Vec<Buffer> {
+        source_buffers(&self.encoder)
+    }
+
+    #[napi(js_name = "repair_packets")]
+    pub fn repair_packets(&self, start_repair_symbol_id: u32, packets: u32) -> Vec<Buffer> {
+        repair_buffers(&self.encoder, start_repair_symbol_id, packets)
+    }
+}
+
+#[napi]
+pub struct Decoder {
+    decoder: raptorq::Decoder,
+}
+
+#[napi]
+impl Decoder {
+    #[napi(constructor)]
+    pub fn new(oti: Uint8Array) -> Result<Self> {
+        let config = parse_oti(&oti)?;
+        Ok(Decoder {
+            decoder: raptorq::Decoder::new(config),
+        })
+    }
+
+    /// Returns the whole object once every block has been decoded.
+    #[napi(js_name = "decode")]
+    pub fn decode(&mut self, packet: Uint8Array) -> Result<Option<Buffer>> {
+        let packet = parse_packet(&packet)?;
+        let result = self.decoder.try_decode(packet).map_err(from_raptorq)?;
+        Ok(result.map(Buffer::from))
+    }
+
+    /// Returns a block as soon as it completes, with the same sizing as the CLI's block output.
+    #[napi(js_name = "decode_block")]
+    pub fn decode_block(&mut self, packet: Uint8Array) -> Result<Option<DecodedBlock>> {
+        let packet = parse_packet(&packet)?;
+        let result = self.decoder.try_decode_block(packet).map_err(from_raptorq)?;
+        Ok(result.map(|block| DecodedBlock {
+            sbn: block.sbn as u32,
+            offset: block.offset as i64,
+            data: block.data.into(),
+        }))
+    }
+}
+
+#[napi]
+pub struct SourceBlockDecoder {
+    decoder: raptorq::SourceBlockDecoder,
+}
+
+#[napi]
+impl SourceBlockDecoder {
+    /// Decodes a single source block of `block_length` bytes (a whole number of symbols).
+    #[napi(constructor)]
+    pub fn new(sbn: u32, oti: Uint8Array, block_length: i64) -> Result<Self> {
+        let sbn = narrow(sbn, "sbn")?;
+        let config = parse_oti(&oti)?;
+        let block_length = u64::try_from(block_length)
+            .map_err(|_| invalid_arg("Block length must not be negative"))?;
+        check_block_length(&config, block_length)?;
+
+        Ok(SourceBlockDecoder {
+            decoder: raptorq::SourceBlockDecoder::new(sbn, &config, block_length),
+        })
+    }
+
+    #[napi(js_name = "decode")]
+    pub fn decode(&mut self, packet: Uint8Array) -> Result<Option<Buffer>> {
+        let packet = parse_packet(&packet)?;
+        let result = self.decoder.try_decode(std::iter::once(packet)).map_err(from_raptorq)?;
+        Ok(result.map(Buffer::from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_then_decode_with_losses() {
+        let data: Vec<u8> = (0..3000).map(|i| (i * 31) as u8).collect();
+        let options = EncodeOptions {
+            symbol_size: 64,
+            num_source_blocks: 2,
+            num_sub_blocks: 1,
+            symbol_alignment: 8,
+        };
+        let encoder = Encoder::new(Uint8Array::new(data.clone()), options).unwrap();
+        let oti = encoder.oti();
+
+        // Lose every fifth packet, the repair packets make up for them
+        let mut decoder = Decoder::new(Uint8Array::new(oti.to_vec())).unwrap();
+        let mut decoded = None;
+        for (i, packet) in encoder.encoded_packets(10).iter().enumerate() {
+            if i % 5 != 0 {
+                decoded = decoded.or(decoder.decode(Uint8Array::new(packet.to_vec())).unwrap());
+            }
+        }
+        assert_eq!(decoded.unwrap().to_vec(), data);
+    }
+}
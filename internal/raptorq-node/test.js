@@ -0,0 +1,38 @@
+// Smoke test for the Node-API addon: loads `raptorq.node`, encodes an object, loses some packets and decodes it again.
+// Usage: node raptorq-node/test.js [path/to/raptorq.node]
+
+import path from "path";
+import { createRequire } from "module";
+import { fileURLToPath } from "url";
+
+const __dirname = path.dirname(fileURLToPath(import.meta.url));
+const addon_path = process.argv[2] ?? path.join(__dirname, "..", "bin", "x86_64-unknown-linux-gnu", "raptorq.node");
+
+console.log("=== RaptorQ Node-API Addon Test ===");
+console.log(`Addon path: ${addon_path}`);
+
+const raptorq = createRequire(import.meta.url)(addon_path);
+
+const data = new Uint8Array(3000);
+for (let i = 0; i < data.length; i++) {
+	data[i] = (i * 31) % 256;
+}
+
+const encoder = new raptorq.Encoder(data, { symbol_size: 64, num_source_blocks: 2, num_sub_blocks: 1, symbol_alignment: 8 });
+const encoding_packets = encoder.encoded_packets(10);
+
+// Lose every fifth packet, the repair packets make up for them
+const decoder = new raptorq.Decoder(encoder.oti());
+let decoded;
+for (const [i, packet] of encoding_packets.entries()) {
+	if (i % 5 !== 0) {
+		decoded ??= decoder.decode(packet);
+	}
+}
+
+if (!decoded || Buffer.compare(decoded, data) !== 0) {
+	console.error("❌ FAILURE: Decoded object does not match the original");
+	process.exit(1);
+}
+
+console.log(`✅ SUCCESS: Decoded ${decoded.length} bytes from ${encoding_packets.length} packets with every fifth one lost`);
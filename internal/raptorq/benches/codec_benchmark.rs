@@ -8,6 +8,7 @@ use rand::Rng;
 use raptorq::SourceBlockDecoder;
 use raptorq::SourceBlockEncoder;
 use raptorq::Symbol;
+use raptorq::SymbolMatrix;
 use raptorq::{ObjectTransmissionInformation, Octet};
 
 fn criterion_benchmark(c: &mut Criterion) {
@@ -62,6 +63,19 @@ fn criterion_benchmark(c: &mut Criterion) {
     });
     group.finish();
 
+    let mut matrix_fma = SymbolMatrix::new(2, symbol_size);
+    matrix_fma.row_mut(0).copy_from_slice(symbol1.as_bytes());
+    matrix_fma.row_mut(1).copy_from_slice(symbol2.as_bytes());
+    let octet1_matrix_fma = octet1.clone();
+    let mut group = c.benchmark_group("SymbolMatrix FMA");
+    group.throughput(Throughput::Bytes(symbol1.len() as u64));
+    group.bench_function(BenchmarkId::new("", ""), move |b| {
+        b.iter(|| {
+            matrix_fma.fused_addassign_mul_scalar(0, 1, &octet1_matrix_fma);
+        })
+    });
+    group.finish();
+
     let elements = 10 * 1024;
     let symbol_size = 512;
     let mut data: Vec<u8> = vec![0; elements];
@@ -7,6 +7,7 @@ use core::cmp::min;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
+use crate::error::Error;
 use crate::rng::rand;
 use crate::systematic_constants::{
     MAX_SOURCE_SYMBOLS_PER_BLOCK, SYSTEMATIC_INDICES_AND_PARAMETERS,
@@ -33,6 +34,16 @@ impl PayloadId {
         }
     }
 
+    pub fn try_new(source_block_number: u8, encoding_symbol_id: u32) -> Result<PayloadId, Error> {
+        if encoding_symbol_id >= 16777216 {
+            return Err(Error::EncodingSymbolIdOutOfRange(encoding_symbol_id));
+        }
+        Ok(PayloadId {
+            source_block_number,
+            encoding_symbol_id,
+        })
+    }
+
     #[allow(clippy::trivially_copy_pass_by_ref)]
     pub fn deserialize(data: &[u8; 4]) -> PayloadId {
         PayloadId {
@@ -59,6 +70,136 @@ impl PayloadId {
     }
 }
 
+/// Bit widths of a compact FEC Payload ID.
+///
+/// The Source Block Number and Encoding Symbol ID are packed most-significant-bit first, SBN
+/// followed by ESI, and the result is padded with zero bits to a whole number of bytes. The
+/// RFC 6330 layout of section 3.2 is [`PayloadIdFormat::RFC6330`] (8-bit SBN, 24-bit ESI). With
+/// 0 SBN bits the SBN is omitted and is always 0.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct PayloadIdFormat {
+    sbn_bits: u8,
+    esi_bits: u8,
+}
+
+impl PayloadIdFormat {
+    /// The 4-byte layout defined in section 3.2
+    pub const RFC6330: PayloadIdFormat = PayloadIdFormat {
+        sbn_bits: 8,
+        esi_bits: 24,
+    };
+
+    pub fn new(sbn_bits: u8, esi_bits: u8) -> PayloadIdFormat {
+        PayloadIdFormat::try_new(sbn_bits, esi_bits).unwrap()
+    }
+
+    /// Like [`new`](Self::new), but fails if `sbn_bits` is above 8 or `esi_bits` is outside 2..=24.
+    pub fn try_new(sbn_bits: u8, esi_bits: u8) -> Result<PayloadIdFormat, Error> {
+        if sbn_bits > 8 {
+            return Err(Error::InvalidPayloadIdFormat(
+                "source block number must use at most 8 bits",
+            ));
+        }
+        if !(2..=24).contains(&esi_bits) {
+            return Err(Error::InvalidPayloadIdFormat(
+                "encoding symbol id must use between 2 and 24 bits",
+            ));
+        }
+        Ok(PayloadIdFormat { sbn_bits, esi_bits })
+    }
+
+    pub fn sbn_bits(&self) -> u8 {
+        self.sbn_bits
+    }
+
+    pub fn esi_bits(&self) -> u8 {
+        self.esi_bits
+    }
+
+    /// Number of bytes a serialized Payload ID occupies
+    pub fn serialized_size(&self) -> usize {
+        (self.sbn_bits as usize + self.esi_bits as usize).div_ceil(8)
+    }
+
+    /// Largest Source Block Number this format can carry
+    pub fn max_source_block_number(&self) -> u8 {
+        ((1u16 << self.sbn_bits) - 1) as u8
+    }
+
+    /// Largest Encoding Symbol ID this format can carry
+    pub fn max_encoding_symbol_id(&self) -> u32 {
+        (1 << self.esi_bits) - 1
+    }
+
+    fn padding_bits(&self) -> u32 {
+        (self.serialized_size() * 8) as u32 - self.sbn_bits as u32 - self.esi_bits as u32
+    }
+
+    /// Writes `payload_id` to the start of `output`, returning the number of bytes written.
+    ///
+    /// Fails if either field does not fit in its bit width, or if `output` is shorter than
+    /// [`serialized_size`](Self::serialized_size).
+    pub fn serialize_into(
+        &self,
+        payload_id: &PayloadId,
+        output: &mut [u8],
+    ) -> Result<usize, Error> {
+        if payload_id.source_block_number > self.max_source_block_number() {
+            return Err(Error::SourceBlockNumberOutOfRange(
+                payload_id.source_block_number,
+            ));
+        }
+        if payload_id.encoding_symbol_id > self.max_encoding_symbol_id() {
+            return Err(Error::EncodingSymbolIdOutOfRange(
+                payload_id.encoding_symbol_id,
+            ));
+        }
+        let size = self.serialized_size();
+        if output.len() < size {
+            return Err(Error::TruncatedBuffer {
+                expected: size,
+                actual: output.len(),
+            });
+        }
+        let packed = (((payload_id.source_block_number as u32) << self.esi_bits)
+            | payload_id.encoding_symbol_id)
+            << self.padding_bits();
+        output[..size].copy_from_slice(&packed.to_be_bytes()[4 - size..]);
+        Ok(size)
+    }
+
+    pub fn serialize(&self, payload_id: &PayloadId) -> Result<Vec<u8>, Error> {
+        let mut serialized = [0; 4];
+        let size = self.serialize_into(payload_id, &mut serialized)?;
+        Ok(Vec::from(&serialized[..size]))
+    }
+
+    /// Reads a Payload ID from the start of `data`. Padding bits are ignored.
+    pub fn deserialize(&self, data: &[u8]) -> Result<PayloadId, Error> {
+        let size = self.serialized_size();
+        if data.len() < size {
+            return Err(Error::TruncatedBuffer {
+                expected: size,
+                actual: data.len(),
+            });
+        }
+        let mut bytes = [0; 4];
+        bytes[4 - size..].copy_from_slice(&data[..size]);
+        let packed = u32::from_be_bytes(bytes) >> self.padding_bits();
+        Ok(PayloadId {
+            source_block_number: (packed >> self.esi_bits) as u8,
+            encoding_symbol_id: packed & self.max_encoding_symbol_id(),
+        })
+    }
+}
+
+impl Default for PayloadIdFormat {
+    fn default() -> PayloadIdFormat {
+        PayloadIdFormat::RFC6330
+    }
+}
+
 /// Contains encoding symbols generated from a source block.
 ///
 /// As defined in section [4.4.2](https://tools.ietf.org/html/rfc6330#section-4.4.2).
@@ -82,6 +223,17 @@ impl EncodingPacket {
         }
     }
 
+    /// Like [`deserialize`](Self::deserialize), but fails on buffers too short to hold a payload ID.
+    pub fn try_deserialize(data: &[u8]) -> Result<EncodingPacket, Error> {
+        if data.len() < 4 {
+            return Err(Error::TruncatedBuffer {
+                expected: 4,
+                actual: data.len(),
+            });
+        }
+        Ok(EncodingPacket::deserialize(data))
+    }
+
     pub fn serialize(&self) -> Vec<u8> {
         let mut serialized = Vec::with_capacity(4 + self.data.len());
         serialized.extend_from_slice(&self.payload_id.serialize());
@@ -89,6 +241,25 @@ impl EncodingPacket {
         return serialized;
     }
 
+    /// Serializes the packet with its Payload ID in a compact `format`.
+    pub fn serialize_with_format(&self, format: &PayloadIdFormat) -> Result<Vec<u8>, Error> {
+        let mut serialized = format.serialize(&self.payload_id)?;
+        serialized.extend(self.data.iter());
+        Ok(serialized)
+    }
+
+    /// Like [`try_deserialize`](Self::try_deserialize), for packets whose Payload ID was
+    /// serialized in a compact `format`.
+    pub fn try_deserialize_with_format(
+        data: &[u8],
+        format: &PayloadIdFormat,
+    ) -> Result<EncodingPacket, Error> {
+        Ok(EncodingPacket {
+            payload_id: format.deserialize(data)?,
+            data: Vec::from(&data[format.serialized_size()..]),
+        })
+    }
+
     /// Retrieves packet payload ID.
     pub fn payload_id(&self) -> &PayloadId {
         &self.payload_id
@@ -146,6 +317,82 @@ impl ObjectTransmissionInformation {
         }
     }
 
+    /// Like [`new`](Self::new), but returns an error instead of panicking, and additionally
+    /// rejects any configuration that the `Encoder` and `Decoder` cannot work with.
+    pub fn try_new(
+        transfer_length: u64,
+        symbol_size: u16,
+        source_blocks: u8,
+        sub_blocks: u16,
+        alignment: u8,
+    ) -> Result<ObjectTransmissionInformation, Error> {
+        let oti = ObjectTransmissionInformation {
+            transfer_length,
+            symbol_size,
+            num_source_blocks: source_blocks,
+            num_sub_blocks: sub_blocks,
+            symbol_alignment: alignment,
+        };
+        oti.validate()?;
+        Ok(oti)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if self.transfer_length == 0 {
+            return Err(Error::InvalidObjectTransmissionInformation(
+                "transfer length must be non-zero",
+            ));
+        }
+        // See errata (https://www.rfc-editor.org/errata/eid5548)
+        if self.transfer_length > 942574504275 {
+            return Err(Error::InvalidObjectTransmissionInformation(
+                "transfer length too large",
+            ));
+        }
+        if self.symbol_alignment == 0 {
+            return Err(Error::InvalidObjectTransmissionInformation(
+                "symbol alignment must be non-zero",
+            ));
+        }
+        if self.symbol_size == 0 || self.symbol_size % self.symbol_alignment as u16 != 0 {
+            return Err(Error::InvalidObjectTransmissionInformation(
+                "symbol size must be a non-zero multiple of the symbol alignment",
+            ));
+        }
+        if self.num_source_blocks == 0 {
+            return Err(Error::InvalidObjectTransmissionInformation(
+                "number of source blocks must be non-zero",
+            ));
+        }
+        // Every source block must hold at least one source symbol
+        if self.num_source_blocks as u64
+            > int_div_ceil(self.transfer_length, self.symbol_size as u64) as u64
+        {
+            return Err(Error::InvalidObjectTransmissionInformation(
+                "number of source blocks must not exceed ceil(F / T)",
+            ));
+        }
+        // See section 4.4.1.2. Each sub-symbol must be at least Al bytes long
+        if self.num_sub_blocks == 0
+            || self.num_sub_blocks > self.symbol_size / self.symbol_alignment as u16
+        {
+            return Err(Error::InvalidObjectTransmissionInformation(
+                "number of sub-blocks must be between 1 and T / Al",
+            ));
+        }
+        // See section 4.4.1.2. "These parameters MUST be set so that ceil(ceil(F/T)/Z) <= K'_max."
+        let symbols_required = int_div_ceil(
+            int_div_ceil(self.transfer_length, self.symbol_size as u64) as u64,
+            self.num_source_blocks as u64,
+        );
+        if symbols_required > MAX_SOURCE_SYMBOLS_PER_BLOCK {
+            return Err(Error::InvalidObjectTransmissionInformation(
+                "too many source symbols per source block",
+            ));
+        }
+        Ok(())
+    }
+
     pub fn deserialize(data: &[u8; 12]) -> ObjectTransmissionInformation {
         ObjectTransmissionInformation {
             transfer_length: ((data[0] as u64) << 32)
@@ -160,6 +407,21 @@ impl ObjectTransmissionInformation {
         }
     }
 
+    /// Like [`deserialize`](Self::deserialize), but fails on truncated buffers and on any
+    /// configuration rejected by [`try_new`](Self::try_new).
+    pub fn try_deserialize(data: &[u8]) -> Result<ObjectTransmissionInformation, Error> {
+        let header: &[u8; 12] = data
+            .get(..12)
+            .and_then(|header| header.try_into().ok())
+            .ok_or(Error::TruncatedBuffer {
+                expected: 12,
+                actual: data.len(),
+            })?;
+        let oti = ObjectTransmissionInformation::deserialize(header);
+        oti.validate()?;
+        Ok(oti)
+    }
+
     pub fn serialize(&self) -> [u8; 12] {
         [
             ((self.transfer_length >> 32) & 0xFF) as u8,
@@ -254,6 +516,260 @@ impl ObjectTransmissionInformation {
         )
     }
 }
+/// How one field of the OTI is carried by an [`OtiProfile`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub enum OtiField {
+    /// The field is omitted, and every object must use this value
+    Fixed(u64),
+    /// The field is transmitted in this many bits
+    Bits(u8),
+}
+
+/// Compact layout of the Object Transmission Information.
+///
+/// Each field of the 12-byte layout of section 3.3 is either transmitted in a (possibly reduced)
+/// number of bits, or omitted and replaced by a value fixed by the profile. Transmitted fields
+/// are packed most-significant-bit first in the order transfer length, FEC Encoding ID, symbol
+/// size, number of source blocks, number of sub-blocks, symbol alignment, and the result is
+/// padded with zero bits to a whole number of bytes. The FEC Encoding ID occupies the 8-bit
+/// reserved field of the RFC 6330 layout; it is written as 0, like
+/// [`ObjectTransmissionInformation::serialize`] does, and ignored when deserializing.
+///
+/// [`OtiProfile::RFC6330`] reproduces [`ObjectTransmissionInformation::serialize`] exactly.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct OtiProfile {
+    transfer_length: OtiField,
+    fec_encoding_id: bool,
+    symbol_size: OtiField,
+    source_blocks: OtiField,
+    sub_blocks: OtiField,
+    symbol_alignment: OtiField,
+}
+
+// Names and widths (in the 12-byte layout) of the OTI fields, in serialization order
+const OTI_FIELDS: [(&str, u8); 6] = [
+    ("transfer length", 40),
+    ("FEC encoding id", 8),
+    ("symbol size", 16),
+    ("number of source blocks", 8),
+    ("number of sub-blocks", 16),
+    ("symbol alignment", 8),
+];
+
+impl OtiProfile {
+    /// The 12-byte layout defined in section 3.3.2 and 3.3.3
+    pub const RFC6330: OtiProfile = OtiProfile {
+        transfer_length: OtiField::Bits(40),
+        fec_encoding_id: true,
+        symbol_size: OtiField::Bits(16),
+        source_blocks: OtiField::Bits(8),
+        sub_blocks: OtiField::Bits(16),
+        symbol_alignment: OtiField::Bits(8),
+    };
+
+    pub fn new(
+        transfer_length: OtiField,
+        fec_encoding_id: bool,
+        symbol_size: OtiField,
+        source_blocks: OtiField,
+        sub_blocks: OtiField,
+        symbol_alignment: OtiField,
+    ) -> OtiProfile {
+        OtiProfile::try_new(
+            transfer_length,
+            fec_encoding_id,
+            symbol_size,
+            source_blocks,
+            sub_blocks,
+            symbol_alignment,
+        )
+        .unwrap()
+    }
+
+    /// Like [`new`](Self::new), but fails if a field is given more bits than it has in the
+    /// 12-byte layout, or a fixed value that does not fit in the field.
+    pub fn try_new(
+        transfer_length: OtiField,
+        fec_encoding_id: bool,
+        symbol_size: OtiField,
+        source_blocks: OtiField,
+        sub_blocks: OtiField,
+        symbol_alignment: OtiField,
+    ) -> Result<OtiProfile, Error> {
+        let profile = OtiProfile {
+            transfer_length,
+            fec_encoding_id,
+            symbol_size,
+            source_blocks,
+            sub_blocks,
+            symbol_alignment,
+        };
+        for (field, (_, max_bits)) in profile.fields().iter().zip(OTI_FIELDS) {
+            match *field {
+                OtiField::Bits(bits) if bits > max_bits => {
+                    return Err(Error::InvalidOtiProfile(
+                        "field transmitted in more bits than the RFC 6330 layout gives it",
+                    ));
+                }
+                OtiField::Fixed(value) if value >> max_bits != 0 => {
+                    return Err(Error::InvalidOtiProfile(
+                        "fixed value does not fit in its field",
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(profile)
+    }
+
+    pub fn transfer_length(&self) -> OtiField {
+        self.transfer_length
+    }
+
+    pub fn fec_encoding_id(&self) -> bool {
+        self.fec_encoding_id
+    }
+
+    pub fn symbol_size(&self) -> OtiField {
+        self.symbol_size
+    }
+
+    pub fn source_blocks(&self) -> OtiField {
+        self.source_blocks
+    }
+
+    pub fn sub_blocks(&self) -> OtiField {
+        self.sub_blocks
+    }
+
+    pub fn symbol_alignment(&self) -> OtiField {
+        self.symbol_alignment
+    }
+
+    fn fields(&self) -> [OtiField; 6] {
+        [
+            self.transfer_length,
+            if self.fec_encoding_id {
+                OtiField::Bits(8)
+            } else {
+                OtiField::Fixed(0)
+            },
+            self.symbol_size,
+            self.source_blocks,
+            self.sub_blocks,
+            self.symbol_alignment,
+        ]
+    }
+
+    fn transmitted_bits(&self) -> u32 {
+        self.fields()
+            .iter()
+            .map(|field| match *field {
+                OtiField::Fixed(_) => 0,
+                OtiField::Bits(bits) => bits as u32,
+            })
+            .sum()
+    }
+
+    /// Number of bytes a serialized OTI occupies. May be 0 if every field is fixed.
+    pub fn serialized_size(&self) -> usize {
+        (self.transmitted_bits() as usize).div_ceil(8)
+    }
+
+    /// Writes `oti` to the start of `output`, returning the number of bytes written.
+    ///
+    /// Fails if a field differs from its fixed value or does not fit in its bits, or if `output`
+    /// is shorter than [`serialized_size`](Self::serialized_size).
+    pub fn serialize_into(
+        &self,
+        oti: &ObjectTransmissionInformation,
+        output: &mut [u8],
+    ) -> Result<usize, Error> {
+        let values = [
+            oti.transfer_length,
+            0,
+            oti.symbol_size as u64,
+            oti.num_source_blocks as u64,
+            oti.num_sub_blocks as u64,
+            oti.symbol_alignment as u64,
+        ];
+        let mut packed: u128 = 0;
+        for ((field, value), (name, _)) in self.fields().iter().zip(values).zip(OTI_FIELDS) {
+            match *field {
+                OtiField::Fixed(fixed) if fixed != value => {
+                    return Err(Error::OtiProfileMismatch(name));
+                }
+                OtiField::Fixed(_) => {}
+                OtiField::Bits(bits) if value >> bits != 0 => {
+                    return Err(Error::OtiProfileMismatch(name));
+                }
+                OtiField::Bits(bits) => packed = (packed << bits) | value as u128,
+            }
+        }
+
+        let size = self.serialized_size();
+        if output.len() < size {
+            return Err(Error::TruncatedBuffer {
+                expected: size,
+                actual: output.len(),
+            });
+        }
+        packed <<= size as u32 * 8 - self.transmitted_bits();
+        output[..size].copy_from_slice(&packed.to_be_bytes()[16 - size..]);
+        Ok(size)
+    }
+
+    pub fn serialize(&self, oti: &ObjectTransmissionInformation) -> Result<Vec<u8>, Error> {
+        let mut serialized = [0; 12];
+        let size = self.serialize_into(oti, &mut serialized)?;
+        Ok(Vec::from(&serialized[..size]))
+    }
+
+    /// Reads an OTI from the start of `data`, failing on truncated buffers and on any
+    /// configuration rejected by [`ObjectTransmissionInformation::try_new`].
+    pub fn deserialize(&self, data: &[u8]) -> Result<ObjectTransmissionInformation, Error> {
+        let size = self.serialized_size();
+        if data.len() < size {
+            return Err(Error::TruncatedBuffer {
+                expected: size,
+                actual: data.len(),
+            });
+        }
+        let mut bytes = [0; 16];
+        bytes[16 - size..].copy_from_slice(&data[..size]);
+        let packed = u128::from_be_bytes(bytes);
+
+        let mut values = [0u64; 6];
+        let mut remaining_bits = size as u32 * 8;
+        for (field, value) in self.fields().iter().zip(values.iter_mut()) {
+            *value = match *field {
+                OtiField::Fixed(fixed) => fixed,
+                OtiField::Bits(bits) => {
+                    remaining_bits -= bits as u32;
+                    ((packed >> remaining_bits) & ((1 << bits) - 1)) as u64
+                }
+            };
+        }
+
+        let oti = ObjectTransmissionInformation {
+            transfer_length: values[0],
+            symbol_size: values[2] as u16,
+            num_source_blocks: values[3] as u8,
+            num_sub_blocks: values[4] as u16,
+            symbol_alignment: values[5] as u8,
+        };
+        oti.validate()?;
+        Ok(oti)
+    }
+}
+
+impl Default for OtiProfile {
+    fn default() -> OtiProfile {
+        OtiProfile::RFC6330
+    }
+}
 
 // Partition[I, J] function, as defined in section 4.4.1.2
 pub fn partition<TI, TJ>(i: TI, j: TJ) -> (u32, u32, u32, u32)
@@ -328,7 +844,10 @@ pub fn intermediate_tuple(
 
 #[cfg(test)]
 mod tests {
-    use crate::{EncodingPacket, ObjectTransmissionInformation, PayloadId};
+    use crate::{
+        EncodingPacket, Error, ObjectTransmissionInformation, OtiField, OtiProfile, PayloadId,
+        PayloadIdFormat,
+    };
     use rand::Rng;
 
     #[test]
@@ -366,4 +885,234 @@ mod tests {
         let deserialized = ObjectTransmissionInformation::deserialize(&oti.serialize());
         assert_eq!(deserialized, oti);
     }
+
+    #[test]
+    fn oti_try_new_rejects_invalid() {
+        assert!(ObjectTransmissionInformation::try_new(942574504275, 65535, 255, 1, 1).is_ok());
+        assert!(ObjectTransmissionInformation::try_new(942574504276, 65535, 255, 1, 1).is_err());
+        assert!(ObjectTransmissionInformation::try_new(0, 1024, 1, 1, 8).is_err());
+        assert!(ObjectTransmissionInformation::try_new(1000, 1020, 1, 1, 8).is_err());
+        assert!(ObjectTransmissionInformation::try_new(1000, 1024, 1, 1, 0).is_err());
+        assert!(ObjectTransmissionInformation::try_new(1000, 1024, 0, 1, 8).is_err());
+        assert!(ObjectTransmissionInformation::try_new(1000, 1024, 1, 0, 8).is_err());
+        assert!(ObjectTransmissionInformation::try_new(1000, 1024, 1, 129, 8).is_err());
+        // ceil(ceil(F/T)/Z) must not exceed K'max
+        assert!(ObjectTransmissionInformation::try_new(56404, 1, 1, 1, 1).is_err());
+        assert!(ObjectTransmissionInformation::try_new(56404, 1, 2, 1, 1).is_ok());
+        // Every source block needs at least one source symbol
+        assert!(ObjectTransmissionInformation::try_new(10, 64, 5, 1, 8).is_err());
+        assert!(ObjectTransmissionInformation::try_new(129, 64, 3, 1, 8).is_ok());
+        assert!(ObjectTransmissionInformation::try_new(128, 64, 3, 1, 8).is_err());
+    }
+
+    #[test]
+    fn oti_try_deserialize() {
+        let oti = ObjectTransmissionInformation::new(10_000, 1024, 2, 4, 8);
+        let serialized = oti.serialize();
+        assert_eq!(
+            ObjectTransmissionInformation::try_deserialize(&serialized),
+            Ok(oti)
+        );
+        assert_eq!(
+            ObjectTransmissionInformation::try_deserialize(&serialized[..11]),
+            Err(Error::TruncatedBuffer {
+                expected: 12,
+                actual: 11
+            })
+        );
+        assert!(ObjectTransmissionInformation::try_deserialize(&[0; 12]).is_err());
+    }
+
+    #[test]
+    fn encoding_packet_try_deserialize() {
+        let packet = EncodingPacket::new(PayloadId::new(1, 2), vec![3, 4]);
+        assert_eq!(
+            EncodingPacket::try_deserialize(&packet.serialize()),
+            Ok(packet)
+        );
+        assert_eq!(
+            EncodingPacket::try_deserialize(&[0, 0, 0]),
+            Err(Error::TruncatedBuffer {
+                expected: 4,
+                actual: 3
+            })
+        );
+        assert_eq!(
+            PayloadId::try_new(0, 16777216),
+            Err(Error::EncodingSymbolIdOutOfRange(16777216))
+        );
+    }
+
+    #[test]
+    fn payload_id_format_rfc6330_matches_serialize() {
+        let payload_id = PayloadId::new(
+            rand::rng().random(),
+            rand::rng().random_range(0..(256 * 256 * 256)),
+        );
+        let format = PayloadIdFormat::RFC6330;
+        assert_eq!(format.serialized_size(), 4);
+        assert_eq!(
+            format.serialize(&payload_id).unwrap(),
+            payload_id.serialize().to_vec()
+        );
+        assert_eq!(format.deserialize(&payload_id.serialize()), Ok(payload_id));
+    }
+
+    #[test]
+    fn payload_id_format_bit_packing() {
+        // 3-bit SBN 0b101 followed by 10-bit ESI 0b11_0000_0001, padded to 16 bits
+        let format = PayloadIdFormat::new(3, 10);
+        let payload_id = PayloadId::new(5, 0x301);
+        let serialized = format.serialize(&payload_id).unwrap();
+        assert_eq!(serialized, vec![0b1011_1000, 0b0000_1000]);
+        assert_eq!(format.deserialize(&serialized), Ok(payload_id));
+
+        // Without an SBN only the ESI is stored
+        let format = PayloadIdFormat::new(0, 8);
+        assert_eq!(format.serialized_size(), 1);
+        assert_eq!(format.serialize(&PayloadId::new(0, 200)), Ok(vec![200]));
+        assert_eq!(format.deserialize(&[200]), Ok(PayloadId::new(0, 200)));
+
+        for sbn_bits in 0..=8 {
+            for esi_bits in 2..=24 {
+                let format = PayloadIdFormat::new(sbn_bits, esi_bits);
+                let payload_id = PayloadId::new(
+                    format.max_source_block_number(),
+                    format.max_encoding_symbol_id(),
+                );
+                let serialized = format.serialize(&payload_id).unwrap();
+                assert_eq!(serialized.len(), format.serialized_size());
+                assert_eq!(format.deserialize(&serialized), Ok(payload_id));
+            }
+        }
+    }
+
+    #[test]
+    fn payload_id_format_validation() {
+        assert!(PayloadIdFormat::try_new(9, 24).is_err());
+        assert!(PayloadIdFormat::try_new(8, 25).is_err());
+        assert!(PayloadIdFormat::try_new(0, 1).is_err());
+        assert!(PayloadIdFormat::try_new(0, 2).is_ok());
+
+        let format = PayloadIdFormat::new(2, 12);
+        assert_eq!(
+            format.serialize(&PayloadId::new(4, 0)),
+            Err(Error::SourceBlockNumberOutOfRange(4))
+        );
+        assert_eq!(
+            format.serialize(&PayloadId::new(0, 4096)),
+            Err(Error::EncodingSymbolIdOutOfRange(4096))
+        );
+        assert_eq!(
+            format.deserialize(&[0]),
+            Err(Error::TruncatedBuffer {
+                expected: 2,
+                actual: 1
+            })
+        );
+    }
+
+    #[test]
+    fn encoding_packet_with_format() {
+        let format = PayloadIdFormat::new(1, 15);
+        let packet = EncodingPacket::new(PayloadId::new(1, 300), vec![3, 4]);
+        let serialized = packet.serialize_with_format(&format).unwrap();
+        assert_eq!(serialized.len(), 4);
+        assert_eq!(
+            EncodingPacket::try_deserialize_with_format(&serialized, &format),
+            Ok(packet)
+        );
+    }
+
+    #[test]
+    fn oti_profile_rfc6330_matches_serialize() {
+        let oti = ObjectTransmissionInformation::new(10_000, 1024, 2, 4, 8);
+        let profile = OtiProfile::RFC6330;
+        assert_eq!(profile.serialized_size(), 12);
+        assert_eq!(profile.serialize(&oti), Ok(oti.serialize().to_vec()));
+        assert_eq!(profile.deserialize(&oti.serialize()), Ok(oti));
+    }
+
+    #[test]
+    fn oti_profile_bit_packing() {
+        // 20-bit transfer length, no FEC Encoding ID, 11-bit symbol size, everything else fixed
+        let profile = OtiProfile::new(
+            OtiField::Bits(20),
+            false,
+            OtiField::Bits(11),
+            OtiField::Fixed(1),
+            OtiField::Fixed(1),
+            OtiField::Fixed(8),
+        );
+        assert_eq!(profile.serialized_size(), 4);
+        let oti = ObjectTransmissionInformation::new(0xABCDE, 1024, 1, 1, 8);
+        let serialized = profile.serialize(&oti).unwrap();
+        // 0xABCDE followed by 0b100_0000_0000 and one padding bit
+        assert_eq!(serialized, vec![0xAB, 0xCD, 0xE8, 0x00]);
+        assert_eq!(profile.deserialize(&serialized), Ok(oti));
+
+        // Every field fixed: nothing is transmitted
+        let profile = OtiProfile::new(
+            OtiField::Fixed(0xABCDE),
+            false,
+            OtiField::Fixed(1024),
+            OtiField::Fixed(1),
+            OtiField::Fixed(1),
+            OtiField::Fixed(8),
+        );
+        assert_eq!(profile.serialize(&oti), Ok(vec![]));
+        assert_eq!(profile.deserialize(&[]), Ok(oti));
+    }
+
+    #[test]
+    fn oti_profile_validation() {
+        assert!(
+            OtiProfile::try_new(
+                OtiField::Bits(41),
+                true,
+                OtiField::Bits(16),
+                OtiField::Bits(8),
+                OtiField::Bits(16),
+                OtiField::Bits(8),
+            )
+            .is_err()
+        );
+        assert!(
+            OtiProfile::try_new(
+                OtiField::Bits(40),
+                true,
+                OtiField::Bits(16),
+                OtiField::Fixed(256),
+                OtiField::Bits(16),
+                OtiField::Bits(8),
+            )
+            .is_err()
+        );
+
+        let profile = OtiProfile::new(
+            OtiField::Bits(16),
+            true,
+            OtiField::Fixed(1024),
+            OtiField::Bits(8),
+            OtiField::Bits(16),
+            OtiField::Bits(8),
+        );
+        assert_eq!(
+            profile.serialize(&ObjectTransmissionInformation::new(1 << 16, 1024, 2, 1, 8)),
+            Err(Error::OtiProfileMismatch("transfer length"))
+        );
+        assert_eq!(
+            profile.serialize(&ObjectTransmissionInformation::new(1000, 512, 1, 1, 8)),
+            Err(Error::OtiProfileMismatch("symbol size"))
+        );
+        assert_eq!(
+            profile.deserialize(&[0; 6]),
+            Err(Error::TruncatedBuffer {
+                expected: 7,
+                actual: 6
+            })
+        );
+        // Deserialized values are validated like any other OTI
+        assert!(profile.deserialize(&[0; 7]).is_err());
+    }
 }
@@ -9,25 +9,47 @@ use alloc::{collections::BTreeSet as Set, vec::Vec};
 
 use crate::base::EncodingPacket;
 use crate::base::ObjectTransmissionInformation;
+#[cfg(not(feature = "python"))]
+use crate::base::PayloadId;
 use crate::base::intermediate_tuple;
 use crate::base::partition;
 use crate::constraint_matrix::enc_indices;
 use crate::constraint_matrix::generate_constraint_matrix;
 use crate::encoder::SPARSE_MATRIX_THRESHOLD;
+use crate::encoder::block_offsets;
+use crate::error::Error;
 use crate::matrix::{BinaryMatrix, DenseBinaryMatrix};
 use crate::octet_matrix::DenseOctetMatrix;
+use crate::octets::add_assign;
 use crate::pi_solver::fused_inverse_mul_symbols;
 use crate::sparse_matrix::SparseBinaryMatrix;
 use crate::symbol::Symbol;
+use crate::symbol_matrix::SymbolMatrix;
 use crate::systematic_constants::num_hdpc_symbols;
 use crate::systematic_constants::num_ldpc_symbols;
 use crate::systematic_constants::{
     calculate_p1, extended_source_block_symbols, num_lt_symbols, num_pi_symbols, systematic_index,
 };
 use crate::util::int_div_ceil;
+#[cfg(all(feature = "parallel", not(feature = "python")))]
+use rayon::prelude::*;
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
 
+// Start of every checkpoint written by Decoder::save
+#[cfg(not(feature = "python"))]
+const CHECKPOINT_MAGIC: &[u8; 4] = b"RQCP";
+#[cfg(not(feature = "python"))]
+const CHECKPOINT_VERSION: u8 = 1;
+
+// State of a source block in a checkpoint
+#[cfg(not(feature = "python"))]
+const BLOCK_PENDING: u8 = 0;
+#[cfg(not(feature = "python"))]
+const BLOCK_DECODED: u8 = 1;
+#[cfg(not(feature = "python"))]
+const BLOCK_DECODED_NOT_KEPT: u8 = 2;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub struct Decoder {
@@ -75,29 +97,363 @@ impl Decoder {
 
     pub fn decode(&mut self, packet: EncodingPacket) -> Option<Vec<u8>> {
         let block_number = packet.payload_id.source_block_number() as usize;
-        if self.blocks[block_number].is_none() {
+        if !self.block_decoders[block_number].decoded {
             self.blocks[block_number] =
                 self.block_decoders[block_number].decode(iter::once(packet));
         }
+        self.combined_result()
+    }
+
+    /// Like [`decode`](Self::decode), but returns an error instead of panicking on packets
+    /// with an out-of-range source block number or a symbol of the wrong length.
+    #[cfg(not(feature = "python"))]
+    pub fn try_decode(&mut self, packet: EncodingPacket) -> Result<Option<Vec<u8>>, Error> {
+        let sbn = packet.payload_id.source_block_number();
+        let block_number = sbn as usize;
+        if block_number >= self.block_decoders.len() {
+            return Err(Error::SourceBlockNumberOutOfRange(sbn));
+        }
+        if !self.block_decoders[block_number].decoded {
+            self.blocks[block_number] =
+                self.block_decoders[block_number].try_decode(iter::once(packet))?;
+        }
+        Ok(self.combined_result())
+    }
+
+    /// Like [`decode`](Self::decode), but returns each source block as soon as it has been
+    /// decoded instead of waiting for the whole object. Returns `None` for packets that do not
+    /// complete a block, including packets for blocks that were already decoded.
+    pub fn decode_block(&mut self, packet: EncodingPacket) -> Option<DecodedBlock> {
+        let block_number = packet.payload_id.source_block_number() as usize;
+        if self.block_decoders[block_number].decoded {
+            return None;
+        }
+        self.blocks[block_number] = self.block_decoders[block_number].decode(iter::once(packet));
+        self.completed_block(block_number)
+    }
+
+    /// Like [`decode_block`](Self::decode_block), but returns an error instead of panicking on
+    /// packets with an out-of-range source block number or a symbol of the wrong length.
+    #[cfg(not(feature = "python"))]
+    pub fn try_decode_block(
+        &mut self,
+        packet: EncodingPacket,
+    ) -> Result<Option<DecodedBlock>, Error> {
+        let sbn = packet.payload_id.source_block_number();
+        let block_number = sbn as usize;
+        if block_number >= self.block_decoders.len() {
+            return Err(Error::SourceBlockNumberOutOfRange(sbn));
+        }
+        if self.block_decoders[block_number].decoded {
+            return Ok(None);
+        }
+        self.blocks[block_number] =
+            self.block_decoders[block_number].try_decode(iter::once(packet))?;
+        Ok(self.completed_block(block_number))
+    }
+
+    /// Like [`decode_block`](Self::decode_block), but writes each source block straight into
+    /// `object` at its offset once it has been decoded, instead of allocating it. `object` must be
+    /// exactly as long as the transfer length, and may for instance be a memory-mapped file.
+    ///
+    /// Returns the source block number of the block completed by this packet, if any. Blocks
+    /// decoded this way are not kept by the decoder, so [`decode`](Self::decode) will not return
+    /// them as part of the object.
+    #[cfg(not(feature = "python"))]
+    pub fn decode_into(&mut self, packet: EncodingPacket, object: &mut [u8]) -> Option<u8> {
+        assert_eq!(
+            object.len() as u64,
+            self.config.transfer_length(),
+            "output buffer must be the length of the object"
+        );
+        let block_number = packet.payload_id.source_block_number() as usize;
+        self.decode_block_into(block_number, packet, object)
+    }
+
+    /// Like [`decode_into`](Self::decode_into), but returns an error instead of panicking on
+    /// packets with an out-of-range source block number or a symbol of the wrong length, or an
+    /// output buffer of the wrong length.
+    #[cfg(not(feature = "python"))]
+    pub fn try_decode_into(
+        &mut self,
+        packet: EncodingPacket,
+        object: &mut [u8],
+    ) -> Result<Option<u8>, Error> {
+        if object.len() as u64 != self.config.transfer_length() {
+            return Err(Error::WrongBufferLength {
+                expected: self.config.transfer_length() as usize,
+                actual: object.len(),
+            });
+        }
+        self.check_packet(&packet)?;
+        let block_number = packet.payload_id.source_block_number() as usize;
+        Ok(self.decode_block_into(block_number, packet, object))
+    }
+
+    #[cfg(not(feature = "python"))]
+    fn decode_block_into(
+        &mut self,
+        block_number: usize,
+        packet: EncodingPacket,
+        object: &mut [u8],
+    ) -> Option<u8> {
+        if self.block_decoders[block_number].decoded {
+            return None;
+        }
+        let (start, end) = block_offsets(&self.config)[block_number];
+        let end = end.min(object.len());
+        let start = start.min(end);
+        let decoder = &mut self.block_decoders[block_number];
+        if decoder.decode_into(iter::once(packet), &mut object[start..end]) {
+            Some(block_number as u8)
+        } else {
+            None
+        }
+    }
+
+    /// Like [`decode_block`](Self::decode_block), but passes the object to `release` piece by
+    /// piece as soon as each piece is known, along with its offset in the object: each source
+    /// symbol as it arrives, and the source symbols that never did once their block is decoded.
+    /// See [`SourceBlockDecoder::decode_progressive`]; padding past the end of the object is
+    /// left out.
+    ///
+    /// Returns the source block number of the block completed by this packet, if any. Blocks
+    /// decoded this way are not kept by the decoder, so [`decode`](Self::decode) will not return
+    /// them as part of the object.
+    #[cfg(not(feature = "python"))]
+    pub fn decode_progressive(
+        &mut self,
+        packet: EncodingPacket,
+        mut release: impl FnMut(u64, &[u8]),
+    ) -> Option<u8> {
+        let block_number = packet.payload_id.source_block_number() as usize;
+        if self.block_decoders[block_number].decoded {
+            return None;
+        }
+        let (start, end) = block_offsets(&self.config)[block_number];
+        let length = end
+            .min(self.config.transfer_length() as usize)
+            .saturating_sub(start);
+        let decoded = self.block_decoders[block_number].decode_progressive(
+            iter::once(packet),
+            |offset, data| {
+                if offset < length {
+                    let data = &data[..data.len().min(length - offset)];
+                    release((start + offset) as u64, data);
+                }
+            },
+        );
+        if decoded {
+            Some(block_number as u8)
+        } else {
+            None
+        }
+    }
+
+    /// Checks that a packet belongs to one of this object's source blocks and carries a symbol of
+    /// the right length, i.e. that [`decode_batch`](Self::decode_batch) will accept it.
+    #[cfg(not(feature = "python"))]
+    pub fn check_packet(&self, packet: &EncodingPacket) -> Result<(), Error> {
+        let sbn = packet.payload_id.source_block_number();
+        if sbn as usize >= self.block_decoders.len() {
+            return Err(Error::SourceBlockNumberOutOfRange(sbn));
+        }
+        if packet.data.len() != self.config.symbol_size() as usize {
+            return Err(Error::WrongSymbolLength {
+                expected: self.config.symbol_size() as usize,
+                actual: packet.data.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Adds a batch of packets, then attempts to decode every source block that received any of
+    /// them. With the `parallel` feature enabled, those blocks are solved concurrently.
+    ///
+    /// Returns the source block numbers completed by this batch in ascending order; their data is
+    /// available from [`decoded_block`](Self::decoded_block). Panics on malformed packets, which
+    /// can be filtered out beforehand with [`check_packet`](Self::check_packet).
+    #[cfg(not(feature = "python"))]
+    pub fn decode_batch<T: IntoIterator<Item = EncodingPacket>>(&mut self, packets: T) -> Vec<u8> {
+        let mut received = vec![false; self.block_decoders.len()];
+        for packet in packets {
+            let block_number = packet.payload_id.source_block_number() as usize;
+            if !self.block_decoders[block_number].decoded {
+                assert_eq!(
+                    packet.data.len(),
+                    self.config.symbol_size() as usize,
+                    "wrong symbol length"
+                );
+                self.block_decoders[block_number].add_packet(packet);
+                received[block_number] = true;
+            }
+        }
+
+        let decode_block = |(block_number, decoder): (usize, &mut SourceBlockDecoder)| {
+            decoder.decode_received().map(|block| (block_number, block))
+        };
+
+        #[cfg(feature = "parallel")]
+        let decoded: Vec<(usize, Vec<u8>)> = self
+            .block_decoders
+            .par_iter_mut()
+            .enumerate()
+            .filter(|(block_number, _)| received[*block_number])
+            .filter_map(decode_block)
+            .collect();
+        #[cfg(not(feature = "parallel"))]
+        let decoded: Vec<(usize, Vec<u8>)> = self
+            .block_decoders
+            .iter_mut()
+            .enumerate()
+            .filter(|(block_number, _)| received[*block_number])
+            .filter_map(decode_block)
+            .collect();
+
+        let mut completed = vec![];
+        for (block_number, block) in decoded {
+            self.blocks[block_number] = Some(block);
+            completed.push(block_number as u8);
+        }
+        completed
+    }
+
+    #[cfg(not(feature = "python"))]
+    pub fn get_config(&self) -> ObjectTransmissionInformation {
+        self.config
+    }
+
+    /// The data of a decoded source block, including the zero padding at the end of the last block.
+    #[cfg(not(feature = "python"))]
+    pub fn decoded_block(&self, sbn: u8) -> Option<&[u8]> {
+        self.blocks.get(sbn as usize)?.as_deref()
+    }
+
+    /// Removes a decoded source block from the decoder and returns its data, as given by
+    /// [`decoded_block`](Self::decoded_block). The block still counts as decoded, but like the
+    /// blocks decoded by [`decode_into`](Self::decode_into) it is no longer part of the object
+    /// returned by [`decode`](Self::decode), nor of checkpoints.
+    #[cfg(not(feature = "python"))]
+    pub fn take_block(&mut self, sbn: u8) -> Option<Vec<u8>> {
+        self.blocks.get_mut(sbn as usize)?.take()
+    }
+
+    /// Reports the decoding progress of every source block, indexed by source block number.
+    #[cfg(not(feature = "python"))]
+    pub fn status(&self) -> Vec<SourceBlockStatus> {
+        self.block_decoders
+            .iter()
+            .map(SourceBlockDecoder::status)
+            .collect()
+    }
+
+    /// Saves the decoding progress as a versioned checkpoint, from which [`load`](Self::load)
+    /// restores the decoder, e.g. to resume a transfer after a restart.
+    ///
+    /// The checkpoint is laid out as `[magic: "RQCP"][version: 1 byte][OTI: 12 bytes]` followed
+    /// by each source block in order, as `[state: 1 byte]` and then:
+    /// - pending: `[failed decode attempts: 4 bytes][symbol count: 4 bytes]` and every symbol
+    ///   received so far as `[ESI: 4 bytes][symbol: T bytes]`
+    /// - decoded: the block, including the padding at the end of the last block
+    /// - decoded but not kept (by [`decode_into`](Self::decode_into) or
+    ///   [`decode_progressive`](Self::decode_progressive)): nothing
+    ///
+    /// Integers are big-endian. The symbols of decoded blocks are not kept, so after a
+    /// [`load`](Self::load) their [`status`](Self::status) reports none received.
+    #[cfg(not(feature = "python"))]
+    pub fn save(&self) -> Vec<u8> {
+        let mut checkpoint = vec![];
+        checkpoint.extend_from_slice(CHECKPOINT_MAGIC);
+        checkpoint.push(CHECKPOINT_VERSION);
+        checkpoint.extend_from_slice(&self.config.serialize());
+        for (decoder, block) in self.block_decoders.iter().zip(self.blocks.iter()) {
+            match block {
+                Some(block) => {
+                    checkpoint.push(BLOCK_DECODED);
+                    checkpoint.extend_from_slice(block);
+                }
+                None if decoder.decoded => checkpoint.push(BLOCK_DECODED_NOT_KEPT),
+                None => {
+                    checkpoint.push(BLOCK_PENDING);
+                    decoder.save_symbols(&mut checkpoint);
+                }
+            }
+        }
+        checkpoint
+    }
+
+    /// Restores a decoder from a checkpoint written by [`save`](Self::save).
+    #[cfg(not(feature = "python"))]
+    pub fn load(checkpoint: &[u8]) -> Result<Decoder, Error> {
+        let mut reader = CheckpointReader { data: checkpoint };
+        if reader.take(CHECKPOINT_MAGIC.len())? != CHECKPOINT_MAGIC {
+            return Err(Error::InvalidCheckpoint("not a decoder checkpoint"));
+        }
+        if reader.u8()? != CHECKPOINT_VERSION {
+            return Err(Error::InvalidCheckpoint("unsupported checkpoint version"));
+        }
+        let config = ObjectTransmissionInformation::try_deserialize(reader.take(12)?)?;
+
+        let mut decoder = Decoder::new(config);
+        for (block_decoder, block) in decoder
+            .block_decoders
+            .iter_mut()
+            .zip(decoder.blocks.iter_mut())
+        {
+            match reader.u8()? {
+                BLOCK_PENDING => block_decoder.load_symbols(&mut reader)?,
+                BLOCK_DECODED => {
+                    let length = block_decoder.symbol_size as usize
+                        * block_decoder.source_block_symbols as usize;
+                    *block = Some(reader.take(length)?.to_vec());
+                    block_decoder.decoded = true;
+                }
+                BLOCK_DECODED_NOT_KEPT => block_decoder.decoded = true,
+                _ => return Err(Error::InvalidCheckpoint("unknown block state")),
+            }
+        }
+        if !reader.data.is_empty() {
+            return Err(Error::InvalidCheckpoint("trailing data"));
+        }
+        Ok(decoder)
+    }
+
+    fn completed_block(&self, block_number: usize) -> Option<DecodedBlock> {
+        let block = self.blocks[block_number].as_ref()?;
+        let (start, end) = block_offsets(&self.config)[block_number];
+        let end = end.min(self.config.transfer_length() as usize);
+        Some(DecodedBlock {
+            sbn: block_number as u8,
+            offset: start as u64,
+            data: block[..end - start].to_vec(),
+        })
+    }
+
+    fn combined_result(&self) -> Option<Vec<u8>> {
         for block in self.blocks.iter() {
             if block.is_none() {
                 return None;
             }
         }
 
-        let mut result = vec![];
+        Some(self.concatenate_blocks())
+    }
+
+    // Copies every block into a vector of exactly the object's length, leaving out the padding
+    fn concatenate_blocks(&self) -> Vec<u8> {
+        let transfer_length = self.config.transfer_length() as usize;
+        let mut result = Vec::with_capacity(transfer_length);
         for block in self.blocks.iter().flatten() {
-            result.extend(block);
+            let length = block.len().min(transfer_length - result.len());
+            result.extend_from_slice(&block[..length]);
         }
-
-        result.truncate(self.config.transfer_length() as usize);
-        Some(result)
+        result
     }
 
     #[cfg(not(feature = "python"))]
     pub fn add_new_packet(&mut self, packet: EncodingPacket) {
         let block_number = packet.payload_id.source_block_number() as usize;
-        if self.blocks[block_number].is_none() {
+        if !self.block_decoders[block_number].decoded {
             self.blocks[block_number] =
                 self.block_decoders[block_number].decode(iter::once(packet));
         }
@@ -111,15 +467,63 @@ impl Decoder {
             }
         }
 
-        let mut result = vec![];
-        for block in self.blocks.iter().flatten() {
-            result.extend(block);
+        Some(self.concatenate_blocks())
+    }
+}
+
+// Reads the fields of a checkpoint one after another
+#[cfg(not(feature = "python"))]
+struct CheckpointReader<'a> {
+    data: &'a [u8],
+}
+
+#[cfg(not(feature = "python"))]
+impl<'a> CheckpointReader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], Error> {
+        if self.data.len() < length {
+            return Err(Error::InvalidCheckpoint("truncated"));
         }
-        result.truncate(self.config.transfer_length() as usize);
-        Some(result)
+        let (field, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(field)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
     }
 }
 
+/// A source block returned by [`Decoder::decode_block`] once it has been decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct DecodedBlock {
+    pub sbn: u8,
+    /// Position of the block's first byte within the object.
+    pub offset: u64,
+    /// The block's bytes, without the padding past the end of the object.
+    pub data: Vec<u8>,
+}
+
+/// Decoding progress of a single source block, as reported by [`SourceBlockDecoder::status`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct SourceBlockStatus {
+    pub source_block_number: u8,
+    /// Number of source symbols (K) in the block.
+    pub source_symbols: u32,
+    /// Number of distinct encoding symbols received so far.
+    pub received_symbols: u32,
+    /// Number of further encoding symbols needed before a decode can even be attempted.
+    pub missing_symbols: u32,
+    /// Number of decode attempts that failed despite at least K symbols having been received.
+    pub failed_decode_attempts: u32,
+    pub decoded: bool,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub struct SourceBlockDecoder {
@@ -133,6 +537,7 @@ pub struct SourceBlockDecoder {
     received_source_symbols: u32,
     received_esi: Set<u32>,
     decoded: bool,
+    failed_decode_attempts: u32,
     sparse_threshold: u32,
 }
 
@@ -155,6 +560,7 @@ impl SourceBlockDecoder {
             received_source_symbols: 0,
             received_esi: Set::new(),
             decoded: false,
+            failed_decode_attempts: 0,
             sparse_threshold: SPARSE_MATRIX_THRESHOLD,
         }
     }
@@ -164,13 +570,27 @@ impl SourceBlockDecoder {
         self.sparse_threshold = value;
     }
 
-    fn unpack_sub_blocks(&self, result: &mut [u8], symbol: &Symbol, symbol_index: usize) {
+    pub fn status(&self) -> SourceBlockStatus {
+        let received_symbols = self.received_esi.len() as u32;
+        SourceBlockStatus {
+            source_block_number: self.source_block_id,
+            source_symbols: self.source_block_symbols,
+            received_symbols,
+            missing_symbols: self.source_block_symbols.saturating_sub(received_symbols),
+            failed_decode_attempts: self.failed_decode_attempts,
+            decoded: self.decoded,
+        }
+    }
+
+    // The offset of every sub-block within the block, and the size of its sub-symbols. See
+    // section 4.4.1.2
+    fn sub_blocks(&self) -> Vec<(usize, usize)> {
         let (tl, ts, nl, ns) = partition(
             (self.symbol_size / self.symbol_alignment as u16) as u32,
             self.num_sub_blocks,
         );
 
-        let mut symbol_offset = 0;
+        let mut sub_blocks = vec![];
         let mut sub_block_offset = 0;
         for sub_block in 0..(nl + ns) {
             let bytes = if sub_block < nl {
@@ -178,38 +598,67 @@ impl SourceBlockDecoder {
             } else {
                 ts as usize * self.symbol_alignment as usize
             };
-            let start = sub_block_offset + bytes * symbol_index;
-            result[start..start + bytes]
-                .copy_from_slice(&symbol.as_bytes()[symbol_offset..symbol_offset + bytes]);
-            symbol_offset += bytes;
+            sub_blocks.push((sub_block_offset, bytes));
             sub_block_offset += bytes * self.source_block_symbols as usize;
         }
+        sub_blocks
+    }
+
+    // Passes each sub-symbol of a source symbol to `unpack` along with its offset in the block
+    fn for_each_sub_symbol(
+        &self,
+        symbol: &Symbol,
+        symbol_index: usize,
+        mut unpack: impl FnMut(usize, &[u8]),
+    ) {
+        let mut symbol_offset = 0;
+        for (sub_block_offset, bytes) in self.sub_blocks() {
+            unpack(
+                sub_block_offset + bytes * symbol_index,
+                &symbol.as_bytes()[symbol_offset..symbol_offset + bytes],
+            );
+            symbol_offset += bytes;
+        }
+    }
+
+    // Writes a symbol's sub-symbols to their places in the block, leaving out any that lie past
+    // the end of `result`
+    fn unpack_sub_blocks(&self, result: &mut [u8], symbol: &Symbol, symbol_index: usize) {
+        self.for_each_sub_symbol(symbol, symbol_index, |start, sub_symbol| {
+            if start < result.len() {
+                let length = sub_symbol.len().min(result.len() - start);
+                result[start..start + length].copy_from_slice(&sub_symbol[..length]);
+            }
+        });
     }
 
     fn try_pi_decode(
         &mut self,
         constraint_matrix: impl BinaryMatrix,
         hdpc_rows: DenseOctetMatrix,
-        symbols: Vec<Symbol>,
-    ) -> Option<Vec<u8>> {
+        symbols: SymbolMatrix,
+        result: &mut [u8],
+    ) -> bool {
         let intermediate_symbols = match fused_inverse_mul_symbols(
             constraint_matrix,
             hdpc_rows,
             symbols,
             self.source_block_symbols,
         ) {
-            (None, _) => return None,
+            (None, _) => {
+                self.failed_decode_attempts += 1;
+                return false;
+            }
             (Some(s), _) => s,
         };
 
-        let mut result = vec![0; self.symbol_size as usize * self.source_block_symbols as usize];
         let lt_symbols = num_lt_symbols(self.source_block_symbols);
         let pi_symbols = num_pi_symbols(self.source_block_symbols);
         let sys_index = systematic_index(self.source_block_symbols);
         let p1 = calculate_p1(self.source_block_symbols);
         for i in 0..self.source_block_symbols as usize {
             if let Some(ref symbol) = self.source_symbols[i] {
-                self.unpack_sub_blocks(&mut result, symbol, i);
+                self.unpack_sub_blocks(result, symbol, i);
             } else {
                 let rebuilt = self.rebuild_source_symbol(
                     &intermediate_symbols,
@@ -219,12 +668,12 @@ impl SourceBlockDecoder {
                     sys_index,
                     p1,
                 );
-                self.unpack_sub_blocks(&mut result, &rebuilt, i);
+                self.unpack_sub_blocks(result, &rebuilt, i);
             }
         }
 
         self.decoded = true;
-        return Some(result);
+        return true;
     }
 
     pub fn decode<T: IntoIterator<Item = EncodingPacket>>(
@@ -236,40 +685,194 @@ impl SourceBlockDecoder {
                 self.source_block_id,
                 packet.payload_id.source_block_number()
             );
+            self.add_packet(packet);
+        }
+
+        self.decode_received()
+    }
+
+    /// Like [`decode`](Self::decode), but returns an error instead of panicking on a packet
+    /// from another source block or with a symbol of the wrong length.
+    ///
+    /// Packets preceding the offending packet have already been added when an error is returned.
+    pub fn try_decode<T: IntoIterator<Item = EncodingPacket>>(
+        &mut self,
+        packets: T,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        for packet in packets {
+            let sbn = packet.payload_id.source_block_number();
+            if sbn != self.source_block_id {
+                return Err(Error::SourceBlockNumberOutOfRange(sbn));
+            }
+            if packet.data.len() != self.symbol_size as usize {
+                return Err(Error::WrongSymbolLength {
+                    expected: self.symbol_size as usize,
+                    actual: packet.data.len(),
+                });
+            }
+            self.add_packet(packet);
+        }
+
+        Ok(self.decode_received())
+    }
+
+    /// Like [`decode`](Self::decode), but writes the block into `result` instead of allocating it,
+    /// and returns whether it has been decoded. `result` may be shorter than the block, leaving out
+    /// the padding at the end of an object's last block, and is left untouched until decoding
+    /// succeeds.
+    pub fn decode_into<T: IntoIterator<Item = EncodingPacket>>(
+        &mut self,
+        packets: T,
+        result: &mut [u8],
+    ) -> bool {
+        assert!(
+            result.len() <= self.symbol_size as usize * self.source_block_symbols as usize,
+            "output buffer is longer than the block"
+        );
+        for packet in packets {
+            assert_eq!(
+                self.source_block_id,
+                packet.payload_id.source_block_number()
+            );
+            self.add_packet(packet);
+        }
+
+        self.decode_received_into(result)
+    }
+
+    /// Like [`decode`](Self::decode), but passes the block to `release` piece by piece as soon as
+    /// each piece is known, and returns whether the block has been decoded.
+    ///
+    /// Every source symbol is released as it arrives. Once the block can be decoded, the source
+    /// symbols that never arrived are released as well, with runs of them merged into one piece.
+    /// Each piece is given with its offset in the block; with sub-blocks, a symbol is split into
+    /// several pieces, one per sub-block. Every byte of the block is released exactly once.
+    pub fn decode_progressive<T: IntoIterator<Item = EncodingPacket>>(
+        &mut self,
+        packets: T,
+        mut release: impl FnMut(usize, &[u8]),
+    ) -> bool {
+        if self.decoded {
+            return true;
+        }
+        for packet in packets {
+            assert_eq!(
+                self.source_block_id,
+                packet.payload_id.source_block_number()
+            );
+            if let Some(i) = self.add_packet(packet) {
+                let symbol = self.source_symbols[i].as_ref().unwrap();
+                self.for_each_sub_symbol(symbol, i, &mut release);
+            }
+        }
 
-            let (payload_id, payload) = packet.split();
-            if self.received_esi.insert(payload_id.encoding_symbol_id()) {
-                if payload_id.encoding_symbol_id() >= self.source_block_symbols {
-                    // Repair symbol
-                    self.repair_packets
-                        .push(EncodingPacket::new(payload_id, payload));
-                } else {
-                    // Source symbol
-                    self.source_symbols[payload_id.encoding_symbol_id() as usize] =
-                        Some(Symbol::new(payload));
-                    self.received_source_symbols += 1;
+        // Nothing is left to release once every source symbol has arrived
+        if self.received_source_symbols == self.source_block_symbols {
+            self.decoded = true;
+            return true;
+        }
+        let Some(block) = self.decode_received() else {
+            return false;
+        };
+        for (sub_block_offset, bytes) in self.sub_blocks() {
+            let mut i = 0;
+            while i < self.source_symbols.len() {
+                if self.source_symbols[i].is_some() {
+                    i += 1;
+                    continue;
                 }
+                let first = i;
+                while i < self.source_symbols.len() && self.source_symbols[i].is_none() {
+                    i += 1;
+                }
+                let start = sub_block_offset + bytes * first;
+                let end = sub_block_offset + bytes * i;
+                release(start, &block[start..end]);
+            }
+        }
+        true
+    }
+
+    // Appends the symbols received so far, in the layout described by Decoder::save
+    #[cfg(not(feature = "python"))]
+    fn save_symbols(&self, checkpoint: &mut Vec<u8>) {
+        checkpoint.extend_from_slice(&self.failed_decode_attempts.to_be_bytes());
+        checkpoint.extend_from_slice(&(self.received_esi.len() as u32).to_be_bytes());
+        for (esi, symbol) in self.source_symbols.iter().enumerate() {
+            if let Some(symbol) = symbol {
+                checkpoint.extend_from_slice(&(esi as u32).to_be_bytes());
+                checkpoint.extend_from_slice(symbol.as_bytes());
             }
         }
+        for packet in self.repair_packets.iter() {
+            checkpoint.extend_from_slice(&packet.payload_id.encoding_symbol_id().to_be_bytes());
+            checkpoint.extend_from_slice(&packet.data);
+        }
+    }
+
+    #[cfg(not(feature = "python"))]
+    fn load_symbols(&mut self, reader: &mut CheckpointReader) -> Result<(), Error> {
+        self.failed_decode_attempts = reader.u32()?;
+        let symbols = reader.u32()?;
+        for _ in 0..symbols {
+            let esi = reader.u32()?;
+            let data = reader.take(self.symbol_size as usize)?;
+            let payload_id = PayloadId::try_new(self.source_block_id, esi)
+                .map_err(|_| Error::InvalidCheckpoint("encoding symbol id out of range"))?;
+            self.add_packet(EncodingPacket::new(payload_id, data.to_vec()));
+        }
+        Ok(())
+    }
+
+    // Returns the index of the packet's symbol if it is a source symbol that had not been received
+    fn add_packet(&mut self, packet: EncodingPacket) -> Option<usize> {
+        let (payload_id, payload) = packet.split();
+        if !self.received_esi.insert(payload_id.encoding_symbol_id()) {
+            return None;
+        }
+        if payload_id.encoding_symbol_id() >= self.source_block_symbols {
+            // Repair symbol
+            self.repair_packets
+                .push(EncodingPacket::new(payload_id, payload));
+            None
+        } else {
+            // Source symbol
+            let i = payload_id.encoding_symbol_id() as usize;
+            self.source_symbols[i] = Some(Symbol::new(payload));
+            self.received_source_symbols += 1;
+            Some(i)
+        }
+    }
+
+    fn decode_received(&mut self) -> Option<Vec<u8>> {
+        if self.received_esi.len() < self.source_block_symbols as usize {
+            return None;
+        }
+        let mut result = vec![0; self.symbol_size as usize * self.source_block_symbols as usize];
+        if self.decode_received_into(&mut result) {
+            Some(result)
+        } else {
+            None
+        }
+    }
 
+    fn decode_received_into(&mut self, result: &mut [u8]) -> bool {
         let num_extended_symbols = extended_source_block_symbols(self.source_block_symbols);
         let num_padding_symbols = num_extended_symbols - self.source_block_symbols;
 
         // Case 1: the number of received packets is insufficient for decoding
         if self.received_esi.len() < self.source_block_symbols as usize {
-            return None;
+            return false;
         }
 
         // Case 2: we have all source symbols and can return them without decoding
         if self.received_source_symbols == self.source_block_symbols {
-            let mut result =
-                vec![0; self.symbol_size as usize * self.source_block_symbols as usize];
             for (i, symbol) in self.source_symbols.iter().enumerate() {
-                self.unpack_sub_blocks(&mut result, symbol.as_ref().unwrap(), i);
+                self.unpack_sub_blocks(result, symbol.as_ref().unwrap(), i);
             }
 
             self.decoded = true;
-            return Some(result);
+            return true;
         }
 
         // Case 3: we may have sufficient symbols to do a standard decoding
@@ -277,59 +880,68 @@ impl SourceBlockDecoder {
         let h = num_hdpc_symbols(self.source_block_symbols) as usize;
 
         let mut encoded_isis = vec![];
-        // See section 5.3.3.4.2. There are S + H zero symbols to start the D vector
-        let mut d = vec![Symbol::zero(self.symbol_size); s + h];
+        // See section 5.3.3.4.2. There are S + H zero symbols to start the D vector, and the
+        // extended padding symbols after the source symbols are zero as well
+        let rows = s
+            + h
+            + self.received_source_symbols as usize
+            + num_padding_symbols as usize
+            + self.repair_packets.len();
+        let mut d = SymbolMatrix::new(rows, self.symbol_size as usize);
+        let mut row = s + h;
         for (i, source) in self.source_symbols.iter().enumerate() {
             if let Some(symbol) = source {
                 encoded_isis.push(i as u32);
-                d.push(symbol.clone());
+                d.row_mut(row).copy_from_slice(symbol.as_bytes());
+                row += 1;
             }
         }
 
-        // Append the extended padding symbols
         for i in self.source_block_symbols..num_extended_symbols {
             encoded_isis.push(i);
-            d.push(Symbol::zero(self.symbol_size));
+            row += 1;
         }
 
         // Append the received repair symbols
         for repair_packet in self.repair_packets.iter() {
             // We need to convert from ESI to ISI
             encoded_isis.push(repair_packet.payload_id.encoding_symbol_id() + num_padding_symbols);
-            d.push(Symbol::new(repair_packet.data.clone()));
+            d.row_mut(row).copy_from_slice(&repair_packet.data);
+            row += 1;
         }
+        assert_eq!(row, rows);
 
         if num_extended_symbols >= self.sparse_threshold {
             let (constraint_matrix, hdpc) = generate_constraint_matrix::<SparseBinaryMatrix>(
                 self.source_block_symbols,
                 &encoded_isis,
             );
-            self.try_pi_decode(constraint_matrix, hdpc, d)
+            self.try_pi_decode(constraint_matrix, hdpc, d, result)
         } else {
             let (constraint_matrix, hdpc) = generate_constraint_matrix::<DenseBinaryMatrix>(
                 self.source_block_symbols,
                 &encoded_isis,
             );
-            self.try_pi_decode(constraint_matrix, hdpc, d)
+            self.try_pi_decode(constraint_matrix, hdpc, d, result)
         }
     }
 
     fn rebuild_source_symbol(
         &self,
-        intermediate_symbols: &[Symbol],
+        intermediate_symbols: &SymbolMatrix,
         source_symbol_id: u32,
         lt_symbols: u32,
         pi_symbols: u32,
         sys_index: u32,
         p1: u32,
     ) -> Symbol {
-        let mut rebuilt = Symbol::zero(self.symbol_size);
+        let mut rebuilt = vec![0; self.symbol_size as usize];
         let tuple = intermediate_tuple(source_symbol_id, lt_symbols, sys_index, p1);
 
         for i in enc_indices(tuple, lt_symbols, pi_symbols, p1) {
-            rebuilt += &intermediate_symbols[i];
+            add_assign(&mut rebuilt, intermediate_symbols.row(i));
         }
-        rebuilt
+        Symbol::new(rebuilt)
     }
 }
 
@@ -352,10 +964,12 @@ mod codec_tests {
     #[cfg(not(feature = "python"))]
     use crate::Decoder;
     #[cfg(not(feature = "python"))]
+    use crate::encoder::block_offsets;
+    #[cfg(not(feature = "python"))]
     use crate::{Encoder, EncoderBuilder};
     use crate::{
-        ObjectTransmissionInformation, SourceBlockDecoder, SourceBlockEncoder,
-        SourceBlockEncodingPlan,
+        EncodingPacket, Error, ObjectTransmissionInformation, PayloadId, SourceBlockDecoder,
+        SourceBlockEncoder, SourceBlockEncodingPlan,
     };
 
     #[cfg(not(feature = "python"))]
@@ -447,6 +1061,465 @@ mod codec_tests {
         assert_eq!(result.unwrap(), data);
     }
 
+    #[cfg(not(feature = "python"))]
+    #[test]
+    fn decode_into_writes_blocks_in_place() {
+        let data: Vec<u8> = (0..10_007).map(|_| rand::rng().random()).collect();
+        // The last block is padded, and sub-blocks split each symbol into uneven sub-symbols
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 496, 3, 4, 8);
+        let encoder = Encoder::new(&data, config);
+
+        let mut packets = encoder.get_encoded_packets(15);
+        packets.shuffle(&mut rand::rng());
+        // Erase 10 packets at random
+        let length = packets.len();
+        packets.truncate(length - 10);
+
+        let mut decoder = Decoder::new(config);
+        let mut object = vec![0; data.len()];
+        let mut completed = vec![];
+        for packet in packets {
+            completed.extend(decoder.decode_into(packet, &mut object));
+        }
+        completed.sort();
+        assert_eq!(completed, (0..config.source_blocks()).collect::<Vec<u8>>());
+        assert_eq!(object, data);
+    }
+
+    #[cfg(not(feature = "python"))]
+    #[test]
+    fn decode_batch_skips_blocks_decoded_in_place() {
+        let data: Vec<u8> = (0..10_007).map(|_| rand::rng().random()).collect();
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 496, 3, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let packets = encoder.get_encoded_packets(5);
+        let (first, rest): (Vec<_>, Vec<_>) = packets
+            .into_iter()
+            .partition(|packet| packet.payload_id().source_block_number() == 0);
+
+        let mut decoder = Decoder::new(config);
+        let mut object = vec![0; data.len()];
+        let mut completed = vec![];
+        for packet in first.iter().cloned() {
+            completed.extend(decoder.decode_into(packet, &mut object));
+        }
+        assert_eq!(completed, vec![0]);
+
+        // Packets for the block already written into the object must not decode it again
+        let mut batch = first.clone();
+        batch.extend(rest);
+        assert_eq!(decoder.decode_batch(batch), vec![1, 2]);
+        assert!(decoder.decoded_block(0).is_none());
+        assert!(decoder.decode_block(first[0].clone()).is_none());
+        assert!(decoder.decode_batch(first).is_empty());
+
+        let (start, end) = block_offsets(&config)[0];
+        assert_eq!(object[start..end], data[start..end]);
+        for sbn in 1..3 {
+            let (start, end) = block_offsets(&config)[sbn];
+            let end = end.min(data.len());
+            assert_eq!(
+                decoder.decoded_block(sbn as u8).unwrap()[..end - start],
+                data[start..end]
+            );
+        }
+    }
+
+    #[cfg(not(feature = "python"))]
+    #[test]
+    fn decode_progressive_releases_every_byte_once() {
+        let data: Vec<u8> = (0..10_007).map(|_| rand::rng().random()).collect();
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 496, 3, 4, 8);
+        let encoder = Encoder::new(&data, config);
+
+        let mut packets = encoder.get_encoded_packets(15);
+        packets.shuffle(&mut rand::rng());
+        // Erase 10 packets at random
+        let length = packets.len();
+        packets.truncate(length - 10);
+
+        let mut decoder = Decoder::new(config);
+        let mut object = vec![0; data.len()];
+        let mut released = vec![0; data.len()];
+        let mut completed = vec![];
+        for packet in packets {
+            let block_pending = !completed.contains(&packet.payload_id().source_block_number());
+            let source = block_pending && packet.payload_id().encoding_symbol_id() < 7;
+            let mut pieces = 0;
+            let sbn = decoder.decode_progressive(packet, |offset, piece| {
+                let offset = offset as usize;
+                object[offset..offset + piece.len()].copy_from_slice(piece);
+                for count in &mut released[offset..offset + piece.len()] {
+                    *count += 1;
+                }
+                pieces += 1;
+            });
+            // A source symbol comes out right away, one piece per sub-block (fewer for the last
+            // symbol, whose padding is left out)
+            if source && sbn.is_none() {
+                assert!((1..=4).contains(&pieces));
+            }
+            completed.extend(sbn);
+        }
+        completed.sort();
+        assert_eq!(completed, vec![0, 1, 2]);
+        assert!(released.iter().all(|&count| count == 1));
+        assert_eq!(object, data);
+    }
+
+    #[test]
+    fn source_block_decode_progressive_releases_missing_symbols_when_solved() {
+        let symbol_size = 8;
+        let data: Vec<u8> = (0..10 * symbol_size).map(|i| i as u8).collect();
+        let config = ObjectTransmissionInformation::new(80, symbol_size as u16, 1, 1, 8);
+        let encoder = SourceBlockEncoder::new(0, &config, &data);
+        let mut decoder = SourceBlockDecoder::new(0, &config, data.len() as u64);
+
+        let mut released = vec![];
+        let mut release = |offset: usize, piece: &[u8]| released.push((offset, piece.to_vec()));
+        // Source symbols 0, 1, 2, 5 and 9, then repair symbols for the rest
+        for esi in [0, 1, 2, 5, 9] {
+            let packet = encoder.source_packets().remove(esi);
+            assert!(!decoder.decode_progressive(iter::once(packet), &mut release));
+        }
+        assert!(decoder.decode_progressive(encoder.repair_packets(0, 5), &mut release));
+
+        let symbol = |esi: usize| (esi * 8, data[esi * 8..(esi + 1) * 8].to_vec());
+        let run = |first: usize, end: usize| (first * 8, data[first * 8..end * 8].to_vec());
+        assert_eq!(
+            released,
+            vec![
+                symbol(0),
+                symbol(1),
+                symbol(2),
+                symbol(5),
+                symbol(9),
+                run(3, 5),
+                run(6, 9)
+            ]
+        );
+    }
+
+    #[cfg(not(feature = "python"))]
+    #[test]
+    fn try_decode_into_rejects_wrong_buffer_length() {
+        let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 2, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let mut decoder = Decoder::new(config);
+        let packet = encoder.get_encoded_packets(0).remove(0);
+
+        assert_eq!(
+            decoder.try_decode_into(packet.clone(), &mut [0; 999]),
+            Err(Error::WrongBufferLength {
+                expected: 1000,
+                actual: 999
+            })
+        );
+        assert_eq!(
+            decoder.try_decode_into(
+                EncodingPacket::new(PayloadId::new(2, 0), vec![0; 64]),
+                &mut [0; 1000]
+            ),
+            Err(Error::SourceBlockNumberOutOfRange(2))
+        );
+        assert_eq!(decoder.try_decode_into(packet, &mut [0; 1000]), Ok(None));
+    }
+
+    #[cfg(not(feature = "python"))]
+    #[test]
+    fn checkpoint_resumes_decoding() {
+        let data: Vec<u8> = (0..3000).map(|i| (i * 7) as u8).collect();
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 3, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let blocks = encoder.get_block_encoders();
+        let mut decoder = Decoder::new(config);
+
+        // Block 0 decoded, block 1 with every other source symbol and a few repair symbols
+        for packet in blocks[0].source_packets() {
+            assert_eq!(decoder.decode(packet), None);
+        }
+        let mut remaining = vec![];
+        for (esi, packet) in blocks[1].source_packets().into_iter().enumerate() {
+            if esi % 2 == 0 {
+                assert_eq!(decoder.decode(packet), None);
+            } else {
+                remaining.push(packet);
+            }
+        }
+        for packet in blocks[1].repair_packets(0, 3) {
+            assert_eq!(decoder.decode(packet), None);
+        }
+
+        let mut resumed = Decoder::load(&decoder.save()).unwrap();
+        assert!(resumed.status()[0].decoded);
+        assert_eq!(resumed.status()[1..], decoder.status()[1..]);
+        assert_eq!(resumed.status()[1].received_symbols, 11);
+
+        remaining.extend(blocks[2].source_packets());
+        let mut result = None;
+        for packet in remaining {
+            result = resumed.decode(packet);
+        }
+        assert_eq!(result, Some(data));
+    }
+
+    #[cfg(not(feature = "python"))]
+    #[test]
+    fn checkpoint_keeps_blocks_decoded_in_place() {
+        let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 2, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let mut decoder = Decoder::new(config);
+        let mut object = vec![0; data.len()];
+        for packet in encoder.get_block_encoders()[1].source_packets() {
+            decoder.decode_into(packet, &mut object);
+        }
+
+        let checkpoint = decoder.save();
+        // Magic, version, OTI, then block 0 pending without symbols and block 1 decoded
+        assert_eq!(checkpoint.len(), 4 + 1 + 12 + 9 + 1);
+        let resumed = Decoder::load(&checkpoint).unwrap();
+        assert!(!resumed.status()[0].decoded);
+        assert!(resumed.status()[1].decoded);
+    }
+
+    #[cfg(not(feature = "python"))]
+    #[test]
+    fn load_rejects_invalid_checkpoints() {
+        let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 2, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let mut decoder = Decoder::new(config);
+        decoder.decode(encoder.get_encoded_packets(0).remove(0));
+        let checkpoint = decoder.save();
+
+        let mut bad_magic = checkpoint.clone();
+        bad_magic[0] = b'X';
+        assert_eq!(
+            Decoder::load(&bad_magic),
+            Err(Error::InvalidCheckpoint("not a decoder checkpoint"))
+        );
+        let mut bad_version = checkpoint.clone();
+        bad_version[4] = 2;
+        assert_eq!(
+            Decoder::load(&bad_version),
+            Err(Error::InvalidCheckpoint("unsupported checkpoint version"))
+        );
+        assert_eq!(
+            Decoder::load(&checkpoint[..checkpoint.len() - 1]),
+            Err(Error::InvalidCheckpoint("truncated"))
+        );
+        let mut trailing = checkpoint.clone();
+        trailing.push(0);
+        assert_eq!(
+            Decoder::load(&trailing),
+            Err(Error::InvalidCheckpoint("trailing data"))
+        );
+    }
+
+    #[test]
+    fn source_block_decode_into_leaves_out_padding() {
+        let symbol_size = 8;
+        let data: Vec<u8> = (0..10 * symbol_size).map(|i| i as u8).collect();
+        let config = ObjectTransmissionInformation::new(75, symbol_size as u16, 1, 1, 8);
+        let encoder = SourceBlockEncoder::new(0, &config, &data);
+        let mut decoder = SourceBlockDecoder::new(0, &config, data.len() as u64);
+
+        // Decode from repair symbols only, which leaves nothing written until the block is solved
+        let mut result = vec![0xff; 75];
+        let mut packets = encoder.repair_packets(0, 12).into_iter();
+        for packet in packets.by_ref().take(9) {
+            assert!(!decoder.decode_into(iter::once(packet), &mut result));
+        }
+        assert_eq!(result, vec![0xff; 75]);
+        assert!(decoder.decode_into(packets, &mut result));
+        assert_eq!(result, &data[..75]);
+    }
+
+    #[cfg(not(feature = "python"))]
+    #[test]
+    fn try_decode_rejects_malformed_packets() {
+        let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 2, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let mut decoder = Decoder::new(config);
+
+        assert_eq!(
+            decoder.try_decode(EncodingPacket::new(PayloadId::new(2, 0), vec![0; 64])),
+            Err(Error::SourceBlockNumberOutOfRange(2))
+        );
+        assert_eq!(
+            decoder.try_decode(EncodingPacket::new(PayloadId::new(0, 0), vec![0; 63])),
+            Err(Error::WrongSymbolLength {
+                expected: 64,
+                actual: 63
+            })
+        );
+
+        let mut result = None;
+        for packet in encoder.get_encoded_packets(0) {
+            result = decoder.try_decode(packet).unwrap();
+        }
+        assert_eq!(result.unwrap(), data);
+    }
+
+    #[cfg(not(feature = "python"))]
+    #[test]
+    fn decode_block_returns_each_block_once() {
+        let data: Vec<u8> = (0..10_000).map(|i| (i % 251) as u8).collect();
+        // 157 symbols of 64 bytes split into blocks of 40, 39, 39 and 39 symbols
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 4, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let mut decoder = Decoder::new(config);
+
+        let mut packets = encoder.get_encoded_packets(5);
+        packets.shuffle(&mut rand::rng());
+        packets.extend(encoder.get_encoded_packets(0));
+
+        let mut blocks = vec![];
+        for packet in packets {
+            blocks.extend(decoder.decode_block(packet));
+        }
+        blocks.sort_by_key(|block| block.sbn);
+
+        assert_eq!(
+            blocks.iter().map(|block| block.sbn).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3]
+        );
+        let mut offset = 0;
+        for block in &blocks {
+            assert_eq!(block.offset, offset);
+            offset += block.data.len() as u64;
+        }
+        assert_eq!(
+            blocks.last().unwrap().data.len(),
+            10_000 - 40 * 64 - 2 * 39 * 64
+        );
+        let decoded: Vec<u8> = blocks.into_iter().flat_map(|block| block.data).collect();
+        assert_eq!(decoded, data);
+    }
+
+    #[cfg(not(feature = "python"))]
+    #[test]
+    fn try_decode_block_rejects_malformed_packets() {
+        let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 2, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let mut decoder = Decoder::new(config);
+
+        assert_eq!(
+            decoder.try_decode_block(EncodingPacket::new(PayloadId::new(2, 0), vec![0; 64])),
+            Err(Error::SourceBlockNumberOutOfRange(2))
+        );
+        assert_eq!(
+            decoder.try_decode_block(EncodingPacket::new(PayloadId::new(1, 0), vec![0; 65])),
+            Err(Error::WrongSymbolLength {
+                expected: 64,
+                actual: 65
+            })
+        );
+
+        let mut decoded = vec![];
+        for packet in encoder.get_encoded_packets(0) {
+            decoded.extend(decoder.try_decode_block(packet).unwrap());
+        }
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[1].offset, 512);
+        assert_eq!(decoded[1].data, &data[512..]);
+    }
+
+    #[cfg(not(feature = "python"))]
+    #[test]
+    fn decode_batch_completes_blocks() {
+        let data = vec![7u8; 1000];
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 8, 4, 1, 1);
+        let encoder = Encoder::new(&data, config);
+        let mut decoder = Decoder::new(config);
+
+        // Drop the first source packet of every block, so that each one needs a repair symbol
+        let mut packets = vec![];
+        for block in encoder.get_block_encoders() {
+            packets.extend(block.source_packets().into_iter().skip(1));
+            packets.extend(block.repair_packets(0, 2));
+        }
+        let malformed = EncodingPacket::new(PayloadId::new(4, 0), vec![0; 8]);
+        assert_eq!(
+            decoder.check_packet(&malformed),
+            Err(Error::SourceBlockNumberOutOfRange(4))
+        );
+        assert!(decoder.check_packet(&packets[0]).is_ok());
+
+        // Blocks that only received part of their packets stay pending
+        let (first, rest): (Vec<_>, Vec<_>) = packets
+            .into_iter()
+            .partition(|packet| packet.payload_id.source_block_number() < 2);
+        assert_eq!(decoder.decode_batch(first), vec![0, 1]);
+        assert!(decoder.decoded_block(2).is_none());
+        assert_eq!(decoder.decode_batch(rest), vec![2, 3]);
+
+        let mut result = vec![];
+        for sbn in 0..4 {
+            result.extend_from_slice(decoder.decoded_block(sbn).unwrap());
+        }
+        result.truncate(data.len());
+        assert_eq!(result, data);
+
+        // A block taken out of the decoder is not decoded again
+        // Block 0 holds 32 of the 125 symbols
+        assert_eq!(decoder.take_block(0).unwrap(), vec![7; 32 * 8]);
+        assert!(decoder.decoded_block(0).is_none());
+        assert!(decoder.status()[0].decoded);
+        let packets = encoder.get_block_encoders()[0].repair_packets(2, 1);
+        assert!(decoder.decode_batch(packets).is_empty());
+    }
+
+    #[cfg(not(feature = "python"))]
+    #[test]
+    fn status_reports_progress() {
+        let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 2, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let mut decoder = Decoder::new(config);
+
+        let status = decoder.status();
+        assert_eq!(status.len(), 2);
+        assert_eq!(status[0].source_symbols, 8);
+        assert_eq!(status[0].missing_symbols, 8);
+        assert_eq!(status[1].source_block_number, 1);
+
+        // Only feed the first block
+        let block_packets = encoder.get_block_encoders()[0].source_packets();
+        for packet in block_packets.iter().skip(1) {
+            assert_eq!(decoder.decode(packet.clone()), None);
+        }
+        let status = decoder.status();
+        assert_eq!(status[0].received_symbols, 7);
+        assert_eq!(status[0].missing_symbols, 1);
+        assert!(!status[0].decoded);
+
+        decoder.decode(block_packets[0].clone());
+        let status = decoder.status();
+        assert!(status[0].decoded);
+        assert_eq!(status[0].missing_symbols, 0);
+        assert_eq!(status[0].failed_decode_attempts, 0);
+        assert!(!status[1].decoded);
+        assert_eq!(status[1].received_symbols, 0);
+    }
+
+    #[test]
+    fn source_block_try_decode_rejects_other_block() {
+        let config = ObjectTransmissionInformation::new(0, 8, 0, 1, 1);
+        let mut decoder = SourceBlockDecoder::new(1, &config, 64);
+        assert_eq!(
+            decoder.try_decode(iter::once(EncodingPacket::new(
+                PayloadId::new(2, 0),
+                vec![0; 8]
+            ))),
+            Err(Error::SourceBlockNumberOutOfRange(2))
+        );
+    }
+
     #[test]
     fn round_trip_dense() {
         round_trip(99_999, 100, false);
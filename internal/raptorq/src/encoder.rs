@@ -1,8 +1,8 @@
 #[cfg(feature = "std")]
-use std::vec::Vec;
+use std::{borrow::Cow, vec::Vec};
 
 #[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
+use alloc::{borrow::Cow, vec::Vec};
 
 use crate::ObjectTransmissionInformation;
 use crate::base::EncodingPacket;
@@ -11,10 +11,13 @@ use crate::base::intermediate_tuple;
 use crate::base::partition;
 use crate::constraint_matrix::generate_constraint_matrix;
 use crate::matrix::DenseBinaryMatrix;
+use crate::octets::add_assign;
 use crate::operation_vector::{SymbolOps, perform_op};
 use crate::pi_solver::fused_inverse_mul_symbols;
 use crate::sparse_matrix::SparseBinaryMatrix;
+#[cfg(test)]
 use crate::symbol::Symbol;
+use crate::symbol_matrix::SymbolMatrix;
 use crate::systematic_constants::extended_source_block_symbols;
 use crate::systematic_constants::num_hdpc_symbols;
 use crate::systematic_constants::num_intermediate_symbols;
@@ -23,11 +26,16 @@ use crate::systematic_constants::num_lt_symbols;
 use crate::systematic_constants::num_pi_symbols;
 use crate::systematic_constants::{calculate_p1, systematic_index};
 use crate::util::int_div_ceil;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
 
 pub const SPARSE_MATRIX_THRESHOLD: u32 = 250;
 
+// Encoding Symbol IDs are 24-bit, see section 3.2
+const MAX_ENCODING_SYMBOL_ID: u32 = (1 << 24) - 1;
+
 #[derive(Default, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub struct EncoderBuilder {
@@ -51,7 +59,7 @@ impl EncoderBuilder {
         self.max_packet_size = bytes;
     }
 
-    pub fn build(&self, data: &[u8]) -> Encoder {
+    pub fn build<'a>(&self, data: &'a [u8]) -> Encoder<'a> {
         let config = ObjectTransmissionInformation::generate_encoding_parameters(
             data.len() as u64,
             self.max_packet_size,
@@ -68,6 +76,20 @@ pub fn calculate_block_offsets(
     data: &[u8],
     config: &ObjectTransmissionInformation,
 ) -> Vec<(usize, usize)> {
+    let blocks = block_offsets(config);
+    if let Some(&(_, end)) = blocks.last() {
+        if end > data.len() {
+            // Should only be possible when Kt * T > F. See third to last paragraph in section 4.4.1.2
+            let kt = int_div_ceil(config.transfer_length(), config.symbol_size() as u64);
+            assert!(kt as usize * config.symbol_size() as usize > data.len());
+        }
+    }
+    blocks
+}
+
+// Same as calculate_block_offsets(), but only needs the transfer length from the config, so that
+// the splits are known before the object itself is available (or, when decoding, without it)
+pub fn block_offsets(config: &ObjectTransmissionInformation) -> Vec<(usize, usize)> {
     let kt = int_div_ceil(config.transfer_length(), config.symbol_size() as u64);
 
     let (kl, ks, zl, zs) = partition(kt, config.source_blocks());
@@ -85,10 +107,6 @@ pub fn calculate_block_offsets(
     if zs > 0 {
         for _ in zl..(zl + zs) {
             let offset = ks as usize * config.symbol_size() as usize;
-            if data_index + offset > data.len() {
-                // Should only be possible when Kt * T > F. See third to last paragraph in section 4.4.1.2
-                assert!(kt as usize * config.symbol_size() as usize > data.len());
-            }
             blocks.push((data_index, (data_index + offset)));
             data_index += offset;
         }
@@ -99,48 +117,53 @@ pub fn calculate_block_offsets(
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
-pub struct Encoder {
+pub struct Encoder<'a> {
     config: ObjectTransmissionInformation,
-    blocks: Vec<SourceBlockEncoder>,
+    blocks: Vec<SourceBlockEncoder<'a>>,
 }
 
-impl Encoder {
-    pub fn new(data: &[u8], config: ObjectTransmissionInformation) -> Encoder {
-        let mut block_encoders = vec![];
-        let mut cached_plan: Option<SourceBlockEncodingPlan> = None;
-        for (i, (start, end)) in calculate_block_offsets(data, &config).drain(..).enumerate() {
-            // Zero pad if necessary
-            let mut padded;
-            let block: &[u8] = if end > data.len() {
-                padded = Vec::from(&data[start..]);
-                padded.extend(vec![0; end - data.len()]);
-                &padded
-            } else {
-                &data[start..end]
-            };
-
-            let symbol_count = block.len() / config.symbol_size() as usize;
-            if cached_plan.is_none()
-                || cached_plan.as_ref().unwrap().source_symbol_count != symbol_count as u16
+impl<'a> Encoder<'a> {
+    pub fn new(data: &'a [u8], config: ObjectTransmissionInformation) -> Encoder<'a> {
+        let blocks = calculate_block_offsets(data, &config);
+
+        // Blocks come in at most two sizes, so their plans are generated once up front and shared
+        let mut plans: Vec<SourceBlockEncodingPlan> = vec![];
+        for (start, end) in blocks.iter() {
+            let symbol_count = ((end - start) / config.symbol_size() as usize) as u16;
+            if !plans
+                .iter()
+                .any(|plan| plan.source_symbol_count == symbol_count)
             {
-                let plan = SourceBlockEncodingPlan::generate(symbol_count as u16);
-                cached_plan = Some(plan);
+                plans.push(SourceBlockEncodingPlan::generate(symbol_count));
             }
-            block_encoders.push(SourceBlockEncoder::with_encoding_plan(
-                i as u8,
-                &config,
-                block,
-                cached_plan.as_ref().unwrap(),
-            ));
         }
 
+        let encode_block = |(i, &(start, end)): (usize, &(usize, usize))| {
+            // The last block may end past the object, and is zero padded by the block encoder. With
+            // more blocks than symbols (which `new` does not reject) it may even start past it.
+            let block = &data[start.min(data.len())..end.min(data.len())];
+
+            let symbol_count = ((end - start) / config.symbol_size() as usize) as u16;
+            let plan = plans
+                .iter()
+                .find(|plan| plan.source_symbol_count == symbol_count)
+                .unwrap();
+            SourceBlockEncoder::with_encoding_plan(i as u8, &config, block, plan)
+        };
+
+        // Source blocks are independent, so they can be encoded concurrently
+        #[cfg(feature = "parallel")]
+        let block_encoders = blocks.par_iter().enumerate().map(encode_block).collect();
+        #[cfg(not(feature = "parallel"))]
+        let block_encoders = blocks.iter().enumerate().map(encode_block).collect();
+
         Encoder {
             config,
             blocks: block_encoders,
         }
     }
 
-    pub fn with_defaults(data: &[u8], maximum_transmission_unit: u16) -> Encoder {
+    pub fn with_defaults(data: &'a [u8], maximum_transmission_unit: u16) -> Encoder<'a> {
         let config = ObjectTransmissionInformation::with_defaults(
             data.len() as u64,
             maximum_transmission_unit,
@@ -154,17 +177,149 @@ impl Encoder {
     }
 
     pub fn get_encoded_packets(&self, repair_packets_per_block: u32) -> Vec<EncodingPacket> {
-        let mut packets = vec![];
-        for encoder in self.blocks.iter() {
-            packets.extend(encoder.source_packets());
+        let block_packets = |encoder: &SourceBlockEncoder| {
+            let mut packets = encoder.source_packets();
             packets.extend(encoder.repair_packets(0, repair_packets_per_block));
+            packets
+        };
+
+        #[cfg(feature = "parallel")]
+        let blocks: Vec<Vec<EncodingPacket>> = self.blocks.par_iter().map(block_packets).collect();
+        #[cfg(not(feature = "parallel"))]
+        let blocks: Vec<Vec<EncodingPacket>> = self.blocks.iter().map(block_packets).collect();
+
+        blocks.into_iter().flatten().collect()
+    }
+
+    pub fn get_block_encoders(&self) -> &Vec<SourceBlockEncoder<'a>> {
+        &self.blocks
+    }
+
+    /// Copies the object, so that the encoder no longer borrows it.
+    pub fn into_owned(self) -> Encoder<'static> {
+        Encoder {
+            config: self.config,
+            blocks: self
+                .blocks
+                .into_iter()
+                .map(SourceBlockEncoder::into_owned)
+                .collect(),
         }
-        packets
     }
+}
 
-    pub fn get_block_encoders(&self) -> &Vec<SourceBlockEncoder> {
+/// Encodes an object whose transfer length is known up front, one source block at a time.
+///
+/// Unlike `Encoder`, the object does not need to be in memory all at once: bytes are pushed as they
+/// become available, and a `SourceBlockEncoder` is returned as soon as each source block is
+/// complete. At most one source block is buffered at any time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct StreamingEncoder {
+    config: ObjectTransmissionInformation,
+    blocks: Vec<(usize, usize)>,
+    next_block: usize,
+    buffer: Vec<u8>,
+    cached_plan: Option<SourceBlockEncodingPlan>,
+}
+
+impl StreamingEncoder {
+    pub fn new(config: ObjectTransmissionInformation) -> StreamingEncoder {
+        StreamingEncoder {
+            config,
+            blocks: block_offsets(&config),
+            next_block: 0,
+            buffer: vec![],
+            cached_plan: None,
+        }
+    }
+
+    pub fn get_config(&self) -> ObjectTransmissionInformation {
+        self.config
+    }
+
+    /// The [start, end) offsets of every source block, see `calculate_block_offsets()`
+    pub fn block_offsets(&self) -> &[(usize, usize)] {
         &self.blocks
     }
+
+    /// Number of object bytes still expected before the current source block is complete, or zero
+    /// once the whole object has been pushed
+    pub fn bytes_needed(&self) -> usize {
+        match self.blocks.get(self.next_block) {
+            Some(&(start, end)) => {
+                let transfer_length = self.config.transfer_length() as usize;
+                end.min(transfer_length) - start - self.buffer.len()
+            }
+            None => 0,
+        }
+    }
+
+    /// Number of object bytes still expected in total
+    pub fn remaining_bytes(&self) -> u64 {
+        match self.blocks.get(self.next_block) {
+            Some(&(start, _)) => {
+                self.config.transfer_length() - start as u64 - self.buffer.len() as u64
+            }
+            None => 0,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.next_block == self.blocks.len()
+    }
+
+    /// Appends the next bytes of the object, returning an encoder for every source block they
+    /// complete. The final source block is zero padded once the transfer length has been reached.
+    /// Blocks that lie entirely within `data` are borrowed from it, the others are owned.
+    ///
+    /// Panics if more bytes are pushed than the transfer length.
+    pub fn push<'a>(&mut self, mut data: &'a [u8]) -> Vec<SourceBlockEncoder<'a>> {
+        assert!(
+            data.len() as u64 <= self.remaining_bytes(),
+            "data exceeds the transfer length"
+        );
+
+        let mut block_encoders = vec![];
+        while !self.is_complete() {
+            let needed = self.bytes_needed();
+            if self.buffer.is_empty() && data.len() >= needed {
+                // Whole block available, encode it without copying into the buffer first
+                let (block, rest) = data.split_at(needed);
+                data = rest;
+                block_encoders.push(self.encode_block(Cow::Borrowed(block)));
+            } else if data.len() >= needed {
+                let (block, rest) = data.split_at(needed);
+                data = rest;
+                let mut buffer = core::mem::take(&mut self.buffer);
+                buffer.extend_from_slice(block);
+                block_encoders.push(self.encode_block(Cow::Owned(buffer)));
+            } else {
+                self.buffer.extend_from_slice(data);
+                break;
+            }
+        }
+
+        block_encoders
+    }
+
+    // The last block may be short of its end, and is zero padded by the block encoder
+    fn encode_block<'a>(&mut self, block: Cow<'a, [u8]>) -> SourceBlockEncoder<'a> {
+        let (start, end) = self.blocks[self.next_block];
+        let symbol_count = (end - start) / self.config.symbol_size() as usize;
+        if self.cached_plan.is_none()
+            || self.cached_plan.as_ref().unwrap().source_symbol_count != symbol_count as u16
+        {
+            self.cached_plan = Some(SourceBlockEncodingPlan::generate(symbol_count as u16));
+        }
+        let encoder = SourceBlockEncoder::from_source_block(
+            self.next_block as u8,
+            SourceBlock::new(&self.config, block),
+            self.cached_plan.as_ref().unwrap(),
+        );
+        self.next_block += 1;
+        encoder
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -179,8 +334,9 @@ impl SourceBlockEncodingPlan {
     // where ceil(data_length / symbol_size) = symbol_count
     pub fn generate(symbol_count: u16) -> SourceBlockEncodingPlan {
         // TODO: refactor pi_solver, so that we don't need this dummy data to generate a plan
-        let symbols = vec![Symbol::new(vec![0]); symbol_count as usize];
-        let (_, ops) = gen_intermediate_symbols(&symbols, 1, SPARSE_MATRIX_THRESHOLD);
+        let source_block =
+            SourceBlock::without_sub_blocks(Cow::Owned(vec![0; symbol_count as usize]), 1);
+        let (_, ops) = gen_intermediate_symbols(&source_block, SPARSE_MATRIX_THRESHOLD);
         SourceBlockEncodingPlan {
             operations: ops.unwrap(),
             source_symbol_count: symbol_count,
@@ -188,62 +344,114 @@ impl SourceBlockEncodingPlan {
     }
 }
 
+// A source block as it was given, from which its source symbols are read on demand. Data that
+// ends part way through the last symbol is zero padded, as the end of the last block of an object
+// is, and with sub-blocks each symbol is gathered from one sub-symbol of every sub-block. See
+// section 4.4.1.2
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
-pub struct SourceBlockEncoder {
-    source_block_id: u8,
-    source_symbols: Vec<Symbol>,
-    intermediate_symbols: Vec<Symbol>,
+struct SourceBlock<'a> {
+    data: Cow<'a, [u8]>,
+    symbol_size: usize,
+    // Size of the sub-symbol each sub-block contributes to a symbol, in sub-block order
+    sub_symbol_sizes: Vec<usize>,
 }
 
-impl SourceBlockEncoder {
-    fn create_symbols(config: &ObjectTransmissionInformation, data: &[u8]) -> Vec<Symbol> {
-        assert_eq!(data.len() % config.symbol_size() as usize, 0);
-        if config.sub_blocks() > 1 {
-            let mut symbols = vec![vec![]; data.len() / config.symbol_size() as usize];
-            let (tl, ts, nl, ns) = partition(
-                (config.symbol_size() / config.symbol_alignment() as u16) as u32,
-                config.sub_blocks(),
-            );
-            // Divide the block into sub-blocks and then concatenate the sub-symbols into symbols
-            // See second to last paragraph in section 4.4.1.2.
-            let mut offset = 0;
-            for sub_block in 0..(nl + ns) {
-                let bytes = if sub_block < nl {
-                    tl as usize * config.symbol_alignment() as usize
-                } else {
-                    ts as usize * config.symbol_alignment() as usize
-                };
-                for symbol in &mut symbols {
-                    symbol.extend_from_slice(&data[offset..offset + bytes]);
-                    offset += bytes;
-                }
-            }
-            assert_eq!(offset, data.len());
-            symbols.drain(..).map(Symbol::new).collect()
+impl<'a> SourceBlock<'a> {
+    fn new(config: &ObjectTransmissionInformation, data: Cow<'a, [u8]>) -> SourceBlock<'a> {
+        if config.sub_blocks() <= 1 {
+            return SourceBlock::without_sub_blocks(data, config.symbol_size() as usize);
+        }
+        let alignment = config.symbol_alignment() as usize;
+        let (tl, ts, nl, ns) = partition(
+            (config.symbol_size() / config.symbol_alignment() as u16) as u32,
+            config.sub_blocks(),
+        );
+        let mut sub_symbol_sizes = vec![tl as usize * alignment; nl as usize];
+        sub_symbol_sizes.extend((0..ns).map(|_| ts as usize * alignment));
+        SourceBlock {
+            data,
+            symbol_size: config.symbol_size() as usize,
+            sub_symbol_sizes,
+        }
+    }
+
+    fn without_sub_blocks(data: Cow<'a, [u8]>, symbol_size: usize) -> SourceBlock<'a> {
+        SourceBlock {
+            data,
+            symbol_size,
+            sub_symbol_sizes: vec![symbol_size],
+        }
+    }
+
+    fn into_owned(self) -> SourceBlock<'static> {
+        SourceBlock {
+            data: Cow::Owned(self.data.into_owned()),
+            symbol_size: self.symbol_size,
+            sub_symbol_sizes: self.sub_symbol_sizes,
+        }
+    }
+
+    fn symbol_count(&self) -> usize {
+        self.data.len().div_ceil(self.symbol_size)
+    }
+
+    // Borrowed from the data, unless the symbol has to be padded or gathered from sub-blocks
+    fn symbol(&self, source_symbol_id: usize) -> Cow<'_, [u8]> {
+        let start = source_symbol_id * self.symbol_size;
+        let end = start + self.symbol_size;
+        if self.sub_symbol_sizes.len() == 1 && end <= self.data.len() {
+            Cow::Borrowed(&self.data[start..end])
         } else {
-            data.chunks(config.symbol_size() as usize)
-                .map(|x| Symbol::new(Vec::from(x)))
-                .collect()
+            let mut symbol = vec![0; self.symbol_size];
+            self.write_symbol(source_symbol_id, &mut symbol);
+            Cow::Owned(symbol)
+        }
+    }
+
+    fn write_symbol(&self, source_symbol_id: usize, symbol: &mut [u8]) {
+        let symbol_count = self.symbol_count();
+        let mut sub_block_start = 0;
+        let mut symbol_offset = 0;
+        for &sub_symbol_size in self.sub_symbol_sizes.iter() {
+            let start = (sub_block_start + source_symbol_id * sub_symbol_size).min(self.data.len());
+            let end = (start + sub_symbol_size).min(self.data.len());
+            let (copied, padding) =
+                symbol[symbol_offset..symbol_offset + sub_symbol_size].split_at_mut(end - start);
+            copied.copy_from_slice(&self.data[start..end]);
+            padding.fill(0);
+            sub_block_start += symbol_count * sub_symbol_size;
+            symbol_offset += sub_symbol_size;
         }
     }
+}
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct SourceBlockEncoder<'a> {
+    source_block_id: u8,
+    // Borrowed from the caller where possible, the source symbols are only copied into the
+    // intermediate symbols and into packets
+    source_block: SourceBlock<'a>,
+    intermediate_symbols: SymbolMatrix,
+}
+
+impl<'a> SourceBlockEncoder<'a> {
+    /// Encodes a source block. If `data` ends part way through its last symbol, the rest of the
+    /// symbol is zero padded.
     pub fn new(
         source_block_id: u8,
         config: &ObjectTransmissionInformation,
-        data: &[u8],
-    ) -> SourceBlockEncoder {
-        let source_symbols = SourceBlockEncoder::create_symbols(config, data);
+        data: &'a [u8],
+    ) -> SourceBlockEncoder<'a> {
+        let source_block = SourceBlock::new(config, Cow::Borrowed(data));
 
-        let (intermediate_symbols, _) = gen_intermediate_symbols(
-            &source_symbols,
-            config.symbol_size() as usize,
-            SPARSE_MATRIX_THRESHOLD,
-        );
+        let (intermediate_symbols, _) =
+            gen_intermediate_symbols(&source_block, SPARSE_MATRIX_THRESHOLD);
 
         SourceBlockEncoder {
             source_block_id,
-            source_symbols,
+            source_block,
             intermediate_symbols: intermediate_symbols.unwrap(),
         }
     }
@@ -251,101 +459,229 @@ impl SourceBlockEncoder {
     pub fn with_encoding_plan(
         source_block_id: u8,
         config: &ObjectTransmissionInformation,
-        data: &[u8],
+        data: &'a [u8],
         plan: &SourceBlockEncodingPlan,
-    ) -> SourceBlockEncoder {
-        let source_symbols = SourceBlockEncoder::create_symbols(config, data);
-        // TODO: this could be more lenient and support anything with the same extended symbol count
-        assert_eq!(source_symbols.len(), plan.source_symbol_count as usize);
+    ) -> SourceBlockEncoder<'a> {
+        SourceBlockEncoder::from_source_block(
+            source_block_id,
+            SourceBlock::new(config, Cow::Borrowed(data)),
+            plan,
+        )
+    }
 
-        let intermediate_symbols = gen_intermediate_symbols_with_plan(
-            &source_symbols,
-            config.symbol_size() as usize,
-            &plan.operations,
+    fn from_source_block(
+        source_block_id: u8,
+        source_block: SourceBlock<'a>,
+        plan: &SourceBlockEncodingPlan,
+    ) -> SourceBlockEncoder<'a> {
+        // TODO: this could be more lenient and support anything with the same extended symbol count
+        assert_eq!(
+            source_block.symbol_count(),
+            plan.source_symbol_count as usize
         );
 
+        let intermediate_symbols =
+            gen_intermediate_symbols_with_plan(&source_block, &plan.operations);
+
         SourceBlockEncoder {
             source_block_id,
-            source_symbols,
+            source_block,
             intermediate_symbols,
         }
     }
 
+    /// Copies the source block, so that the encoder no longer borrows it.
+    pub fn into_owned(self) -> SourceBlockEncoder<'static> {
+        SourceBlockEncoder {
+            source_block_id: self.source_block_id,
+            source_block: self.source_block.into_owned(),
+            intermediate_symbols: self.intermediate_symbols,
+        }
+    }
+
+    /// The source packets of the block, each holding its own copy of its symbol. See
+    /// [`source_symbol`](Self::source_symbol) and
+    /// [`write_source_packet`](Self::write_source_packet) to avoid the copies.
     pub fn source_packets(&self) -> Vec<EncodingPacket> {
-        let mut esi: i32 = -1;
-        self.source_symbols
-            .iter()
-            .map(|symbol| {
-                esi += 1;
+        (0..self.source_symbol_count())
+            .map(|esi| {
                 EncodingPacket::new(
-                    PayloadId::new(self.source_block_id, esi as u32),
-                    symbol.as_bytes().to_vec(),
+                    PayloadId::new(self.source_block_id, esi),
+                    self.source_symbol(esi).into_owned(),
                 )
             })
             .collect()
     }
 
+    /// The source symbol with the given ESI. It is borrowed from the data the encoder was given,
+    /// unless it is the zero padded last symbol or is gathered from sub-blocks.
+    pub fn source_symbol(&self, source_symbol_id: u32) -> Cow<'_, [u8]> {
+        assert!(source_symbol_id < self.source_symbol_count());
+        self.source_block.symbol(source_symbol_id as usize)
+    }
+
+    /// Number of source symbols (K) in the block.
+    pub fn source_symbol_count(&self) -> u32 {
+        self.source_block.symbol_count() as u32
+    }
+
+    /// Size of each symbol in bytes.
+    pub fn symbol_size(&self) -> usize {
+        self.intermediate_symbols.symbol_size()
+    }
+
+    /// Writes a source packet, serialized as by [`EncodingPacket::serialize`], to the start of
+    /// `buffer` and returns its length. Panics if `buffer` is shorter than 4 + symbol size bytes.
+    pub fn write_source_packet(&self, source_symbol_id: u32, buffer: &mut [u8]) -> usize {
+        assert!(source_symbol_id < self.source_symbol_count());
+        let symbol_size = self.symbol_size();
+        let payload_id = PayloadId::new(self.source_block_id, source_symbol_id);
+        buffer[..4].copy_from_slice(&payload_id.serialize());
+        self.source_block
+            .write_symbol(source_symbol_id as usize, &mut buffer[4..4 + symbol_size]);
+        4 + symbol_size
+    }
+
+    /// Writes a repair packet, serialized as by [`EncodingPacket::serialize`], to the start of
+    /// `buffer` and returns its length. The symbol is computed in place, without allocating.
+    /// Panics if `buffer` is shorter than 4 + symbol size bytes.
+    pub fn write_repair_packet(&self, repair_symbol_id: u32, buffer: &mut [u8]) -> usize {
+        let symbol_size = self.symbol_size();
+        let payload_id = PayloadId::new(
+            self.source_block_id,
+            self.source_symbol_count() + repair_symbol_id,
+        );
+        buffer[..4].copy_from_slice(&payload_id.serialize());
+        self.repair_symbol_into(repair_symbol_id, &mut buffer[4..4 + symbol_size]);
+        4 + symbol_size
+    }
+
     // See section 5.3.4
     pub fn repair_packets(&self, start_repair_symbol_id: u32, packets: u32) -> Vec<EncodingPacket> {
-        let start_encoding_symbol_id = start_repair_symbol_id
-            + extended_source_block_symbols(self.source_symbols.len() as u32);
-        let mut result = vec![];
-        let lt_symbols = num_lt_symbols(self.source_symbols.len() as u32);
-        let sys_index = systematic_index(self.source_symbols.len() as u32);
-        let p1 = calculate_p1(self.source_symbols.len() as u32);
-        for i in 0..packets {
-            let tuple = intermediate_tuple(start_encoding_symbol_id + i, lt_symbols, sys_index, p1);
-            result.push(EncodingPacket::new(
-                PayloadId::new(
-                    self.source_block_id,
-                    self.source_symbols.len() as u32 + start_repair_symbol_id + i,
-                ),
-                enc(
-                    self.source_symbols.len() as u32,
-                    &self.intermediate_symbols,
-                    tuple,
-                )
-                .into_bytes(),
-            ));
-        }
+        let repair_packet = |i: u32| self.repair_packet(start_repair_symbol_id + i);
+
+        // Every repair symbol is computed independently from the intermediate symbols
+        #[cfg(feature = "parallel")]
+        let result = (0..packets).into_par_iter().map(repair_packet).collect();
+        #[cfg(not(feature = "parallel"))]
+        let result = (0..packets).map(repair_packet).collect();
         result
     }
+
+    /// Returns an iterator that computes repair packets on demand, starting at
+    /// `start_repair_symbol_id` (numbered as in [`repair_packets`](Self::repair_packets)).
+    ///
+    /// The iterator only ends once the 24-bit Encoding Symbol ID space is exhausted, so for rateless
+    /// use it can be consumed for as long as the receiver needs more symbols.
+    pub fn repair_packet_iter(&self, start_repair_symbol_id: u32) -> RepairPacketIter<'_> {
+        RepairPacketIter {
+            encoder: self,
+            next_repair_symbol_id: start_repair_symbol_id,
+        }
+    }
+
+    fn max_repair_symbol_id(&self) -> u32 {
+        MAX_ENCODING_SYMBOL_ID - self.source_symbol_count()
+    }
+
+    fn repair_packet(&self, repair_symbol_id: u32) -> EncodingPacket {
+        let mut symbol = vec![0; self.symbol_size()];
+        self.repair_symbol_into(repair_symbol_id, &mut symbol);
+        EncodingPacket::new(
+            PayloadId::new(
+                self.source_block_id,
+                self.source_symbol_count() + repair_symbol_id,
+            ),
+            symbol,
+        )
+    }
+
+    fn repair_symbol_into(&self, repair_symbol_id: u32, result: &mut [u8]) {
+        let source_block_symbols = self.source_symbol_count();
+        // Repair symbols are numbered after the extended source block, see section 5.3.1
+        let encoding_symbol_id =
+            repair_symbol_id + extended_source_block_symbols(source_block_symbols);
+        let tuple = intermediate_tuple(
+            encoding_symbol_id,
+            num_lt_symbols(source_block_symbols),
+            systematic_index(source_block_symbols),
+            calculate_p1(source_block_symbols),
+        );
+        enc_into(
+            source_block_symbols,
+            &self.intermediate_symbols,
+            tuple,
+            result,
+        );
+    }
 }
 
-#[allow(non_snake_case)]
-fn create_d(
-    source_block: &[Symbol],
-    symbol_size: usize,
-    extended_source_symbols: usize,
-) -> Vec<Symbol> {
-    let L = num_intermediate_symbols(source_block.len() as u32);
-    let S = num_ldpc_symbols(source_block.len() as u32);
-    let H = num_hdpc_symbols(source_block.len() as u32);
+/// Lazily computed repair packets of a source block, see
+/// [`SourceBlockEncoder::repair_packet_iter`].
+#[derive(Clone, Debug)]
+pub struct RepairPacketIter<'a> {
+    encoder: &'a SourceBlockEncoder<'a>,
+    next_repair_symbol_id: u32,
+}
 
-    let mut D = Vec::with_capacity(L as usize);
-    for _ in 0..(S + H) {
-        D.push(Symbol::zero(symbol_size));
+impl RepairPacketIter<'_> {
+    /// Like [`next`](Iterator::next), but writes the packet into `buffer` as
+    /// [`SourceBlockEncoder::write_repair_packet`] does, returning its length.
+    pub fn write_next(&mut self, buffer: &mut [u8]) -> Option<usize> {
+        let repair_symbol_id = self.advance()?;
+        Some(self.encoder.write_repair_packet(repair_symbol_id, buffer))
     }
-    for symbol in source_block {
-        D.push(symbol.clone());
+
+    fn advance(&mut self) -> Option<u32> {
+        if self.next_repair_symbol_id > self.encoder.max_repair_symbol_id() {
+            return None;
+        }
+        let repair_symbol_id = self.next_repair_symbol_id;
+        self.next_repair_symbol_id += 1;
+        Some(repair_symbol_id)
     }
-    // Extend the source block with padding. See section 5.3.2
-    for _ in 0..(extended_source_symbols - source_block.len()) {
-        D.push(Symbol::zero(symbol_size));
+}
+
+impl Iterator for RepairPacketIter<'_> {
+    type Item = EncodingPacket;
+
+    fn next(&mut self) -> Option<EncodingPacket> {
+        let repair_symbol_id = self.advance()?;
+        Some(self.encoder.repair_packet(repair_symbol_id))
     }
-    assert_eq!(D.len(), L as usize);
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.encoder.max_repair_symbol_id() + 1)
+            .saturating_sub(self.next_repair_symbol_id) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+#[allow(non_snake_case)]
+fn create_d(source_block: &SourceBlock, extended_source_symbols: usize) -> SymbolMatrix {
+    let source_symbols = source_block.symbol_count();
+    let L = num_intermediate_symbols(source_symbols as u32);
+    let S = num_ldpc_symbols(source_symbols as u32);
+    let H = num_hdpc_symbols(source_symbols as u32);
+
+    // The S + H rows before the source symbols, and the padding symbols extending the source
+    // block after them (see section 5.3.2), stay zero
+    let mut D = SymbolMatrix::new(L as usize, source_block.symbol_size);
+    for i in 0..source_symbols {
+        source_block.write_symbol(i, D.row_mut((S + H) as usize + i));
+    }
+    assert_eq!(D.rows(), (S + H) as usize + extended_source_symbols);
     D
 }
 
 // See section 5.3.3.4
 #[allow(non_snake_case)]
 fn gen_intermediate_symbols(
-    source_block: &[Symbol],
-    symbol_size: usize,
+    source_block: &SourceBlock,
     sparse_threshold: u32,
-) -> (Option<Vec<Symbol>>, Option<Vec<SymbolOps>>) {
-    let extended_source_symbols = extended_source_block_symbols(source_block.len() as u32);
-    let D = create_d(source_block, symbol_size, extended_source_symbols as usize);
+) -> (Option<SymbolMatrix>, Option<Vec<SymbolOps>>) {
+    let extended_source_symbols = extended_source_block_symbols(source_block.symbol_count() as u32);
+    let D = create_d(source_block, extended_source_symbols as usize);
 
     let indices: Vec<u32> = (0..extended_source_symbols).collect();
     if extended_source_symbols >= sparse_threshold {
@@ -361,12 +697,11 @@ fn gen_intermediate_symbols(
 
 #[allow(non_snake_case)]
 fn gen_intermediate_symbols_with_plan(
-    source_block: &[Symbol],
-    symbol_size: usize,
+    source_block: &SourceBlock,
     operation_vector: &[SymbolOps],
-) -> Vec<Symbol> {
-    let extended_source_symbols = extended_source_block_symbols(source_block.len() as u32);
-    let mut D = create_d(source_block, symbol_size, extended_source_symbols as usize);
+) -> SymbolMatrix {
+    let extended_source_symbols = extended_source_block_symbols(source_block.symbol_count() as u32);
+    let mut D = create_d(source_block, extended_source_symbols as usize);
 
     for op in operation_vector {
         perform_op(op, &mut D);
@@ -376,11 +711,29 @@ fn gen_intermediate_symbols_with_plan(
 
 // Enc[] function, as defined in section 5.3.5.3
 #[allow(clippy::many_single_char_names)]
+#[cfg(test)]
 fn enc(
     source_block_symbols: u32,
-    intermediate_symbols: &[Symbol],
+    intermediate_symbols: &SymbolMatrix,
     source_tuple: (u32, u32, u32, u32, u32, u32),
 ) -> Symbol {
+    let mut result = vec![0; intermediate_symbols.symbol_size()];
+    enc_into(
+        source_block_symbols,
+        intermediate_symbols,
+        source_tuple,
+        &mut result,
+    );
+    Symbol::new(result)
+}
+
+// Writes the encoding symbol for the given tuple into `result`, which must be exactly one symbol long
+fn enc_into(
+    source_block_symbols: u32,
+    intermediate_symbols: &SymbolMatrix,
+    source_tuple: (u32, u32, u32, u32, u32, u32),
+    result: &mut [u8],
+) {
     let w = num_lt_symbols(source_block_symbols);
     let p = num_pi_symbols(source_block_symbols);
     let p1 = calculate_p1(source_block_symbols);
@@ -392,27 +745,25 @@ fn enc(
     assert!(1 <= a1 && a < w);
     assert!(b1 < w);
 
-    let mut result = intermediate_symbols[b as usize].clone();
+    result.copy_from_slice(intermediate_symbols.row(b as usize));
     for _ in 1..d {
         b = (b + a) % w;
-        result += &intermediate_symbols[b as usize];
+        add_assign(result, intermediate_symbols.row(b as usize));
     }
 
     while b1 >= p {
         b1 = (b1 + a1) % p1;
     }
 
-    result += &intermediate_symbols[(w + b1) as usize];
+    add_assign(result, intermediate_symbols.row((w + b1) as usize));
 
     for _ in 1..d1 {
         b1 = (b1 + a1) % p1;
         while b1 >= p {
             b1 = (b1 + a1) % p1;
         }
-        result += &intermediate_symbols[(w + b1) as usize];
+        add_assign(result, intermediate_symbols.row((w + b1) as usize));
     }
-
-    result
 }
 
 #[cfg(feature = "std")]
@@ -425,7 +776,6 @@ mod tests {
 
     use crate::PayloadId;
     use crate::base::intermediate_tuple;
-    use crate::symbol::Symbol;
     use crate::systematic_constants::num_lt_symbols;
     use crate::systematic_constants::num_pi_symbols;
     use crate::systematic_constants::{
@@ -447,13 +797,8 @@ mod tests {
         data
     }
 
-    fn gen_test_symbols() -> Vec<Symbol> {
-        let mut source_block: Vec<Symbol> = vec![];
-        for _ in 0..NUM_SYMBOLS {
-            let data = gen_test_data(SYMBOL_SIZE);
-            source_block.push(Symbol::new(data));
-        }
-        source_block
+    fn gen_test_block() -> Vec<u8> {
+        gen_test_data(SYMBOL_SIZE * NUM_SYMBOLS as usize)
     }
 
     #[test]
@@ -467,20 +812,22 @@ mod tests {
     }
 
     fn enc_constraint(sparse_threshold: u32) {
-        let source_symbols = gen_test_symbols();
+        let source_block = gen_test_block();
 
-        let (intermediate_symbols, _) =
-            gen_intermediate_symbols(&source_symbols, SYMBOL_SIZE, sparse_threshold);
+        let (intermediate_symbols, _) = gen_intermediate_symbols(
+            &SourceBlock::without_sub_blocks(Cow::Borrowed(&source_block), SYMBOL_SIZE),
+            sparse_threshold,
+        );
         let intermediate_symbols = intermediate_symbols.unwrap();
 
         let lt_symbols = num_lt_symbols(NUM_SYMBOLS);
         let sys_index = systematic_index(NUM_SYMBOLS);
         let p1 = calculate_p1(NUM_SYMBOLS);
         // See section 5.3.3.4.1, item 1.
-        for (i, source_symbol) in source_symbols.iter().enumerate() {
+        for (i, source_symbol) in source_block.chunks(SYMBOL_SIZE).enumerate() {
             let tuple = intermediate_tuple(i as u32, lt_symbols, sys_index, p1);
             let encoded = enc(NUM_SYMBOLS, &intermediate_symbols, tuple);
-            assert_eq!(source_symbol, &encoded);
+            assert_eq!(source_symbol, encoded.as_bytes());
         }
     }
 
@@ -496,8 +843,10 @@ mod tests {
 
     #[allow(non_snake_case)]
     fn ldpc_constraint(sparse_threshold: u32) {
-        let (intermediate_symbols, _) =
-            gen_intermediate_symbols(&gen_test_symbols(), SYMBOL_SIZE, sparse_threshold);
+        let (intermediate_symbols, _) = gen_intermediate_symbols(
+            &SourceBlock::without_sub_blocks(Cow::Owned(gen_test_block()), SYMBOL_SIZE),
+            sparse_threshold,
+        );
         let C = intermediate_symbols.unwrap();
         let S = num_ldpc_symbols(NUM_SYMBOLS) as usize;
         let P = num_pi_symbols(NUM_SYMBOLS) as usize;
@@ -507,30 +856,30 @@ mod tests {
         // See section 5.3.3.3
         let mut D = vec![];
         for i in 0..S {
-            D.push(C[B + i].clone());
+            D.push(C.row(B + i).to_vec());
         }
 
         for i in 0..B {
             let a = 1 + i / S;
             let b = i % S;
-            D[b] += &C[i];
+            add_assign(&mut D[b], C.row(i));
 
             let b = (b + a) % S;
-            D[b] += &C[i];
+            add_assign(&mut D[b], C.row(i));
 
             let b = (b + a) % S;
-            D[b] += &C[i];
+            add_assign(&mut D[b], C.row(i));
         }
 
         for i in 0..S {
             let a = i % P;
             let b = (i + 1) % P;
-            D[i] += &C[W + a];
-            D[i] += &C[W + b];
+            add_assign(&mut D[i], C.row(W + a));
+            add_assign(&mut D[i], C.row(W + b));
         }
 
         for i in 0..S {
-            assert_eq!(Symbol::zero(SYMBOL_SIZE), D[i]);
+            assert_eq!(vec![0; SYMBOL_SIZE], D[i]);
         }
     }
 
@@ -561,6 +910,66 @@ mod tests {
         );
     }
 
+    #[test]
+    fn short_block_is_zero_padded() {
+        let data = gen_test_data(75);
+        let mut padded = data.clone();
+        padded.resize(80, 0);
+        for sub_blocks in [1, 2] {
+            let config = ObjectTransmissionInformation::new(75, 8, 1, sub_blocks, 2);
+            let encoder = SourceBlockEncoder::new(0, &config, &data);
+            let padded_encoder = SourceBlockEncoder::new(0, &config, &padded);
+            assert_eq!(encoder.source_symbol_count(), 10);
+            assert_eq!(encoder.source_packets(), padded_encoder.source_packets());
+            assert_eq!(
+                encoder.repair_packets(0, 10),
+                padded_encoder.repair_packets(0, 10)
+            );
+            let mut buffer = [0xff; 12];
+            for packet in encoder.source_packets() {
+                let esi = packet.payload_id.encoding_symbol_id();
+                assert_eq!(&encoder.source_symbol(esi)[..], packet.data());
+                assert_eq!(encoder.write_source_packet(esi, &mut buffer), 12);
+                assert_eq!(&buffer[..], &packet.serialize()[..]);
+            }
+            // Only whole symbols of a block without sub-blocks can be borrowed from the data
+            let borrowed = matches!(encoder.source_symbol(0), Cow::Borrowed(_));
+            assert_eq!(borrowed, sub_blocks == 1);
+            assert!(matches!(encoder.source_symbol(9), Cow::Owned(_)));
+        }
+    }
+
+    #[test]
+    fn repair_packet_iter_matches_repair_packets() {
+        let data = gen_test_data(40);
+        let config = ObjectTransmissionInformation::new(0, 4, 1, 1, 1);
+        let encoder = SourceBlockEncoder::new(0, &config, &data);
+
+        let iterated: Vec<EncodingPacket> = encoder.repair_packet_iter(3).take(20).collect();
+        assert_eq!(iterated, encoder.repair_packets(3, 20));
+
+        let mut buffer = [0u8; 8];
+        let mut iter = encoder.repair_packet_iter(3);
+        for packet in iterated.iter() {
+            assert_eq!(iter.write_next(&mut buffer), Some(8));
+            assert_eq!(&buffer[..], &packet.serialize()[..]);
+        }
+        for (i, packet) in encoder.source_packets().iter().enumerate() {
+            assert_eq!(encoder.write_source_packet(i as u32, &mut buffer), 8);
+            assert_eq!(&buffer[..], &packet.serialize()[..]);
+        }
+
+        // The iterator stops at the largest Encoding Symbol ID
+        let last_repair_symbol_id = (1 << 24) - 1 - 10;
+        let mut iter = encoder.repair_packet_iter(last_repair_symbol_id);
+        assert_eq!(iter.size_hint(), (1, Some(1)));
+        assert_eq!(
+            iter.next().unwrap().payload_id.encoding_symbol_id(),
+            (1 << 24) - 1
+        );
+        assert!(iter.next().is_none());
+    }
+
     #[cfg(not(feature = "python"))]
     #[test]
     fn test_builder() {
@@ -610,6 +1019,42 @@ mod tests {
         assert_eq!(data[..], padded_data[..data_size]);
     }
 
+    #[cfg(not(feature = "python"))]
+    #[test]
+    fn streaming_encoder_matches_encoder() {
+        let data = gen_test_data(1001);
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 8, 3, 1, 1);
+        let encoder = Encoder::new(&data, config);
+
+        let mut streaming = StreamingEncoder::new(config);
+        assert_eq!(
+            streaming.block_offsets(),
+            &calculate_block_offsets(&data, &config)[..]
+        );
+        let mut block_encoders = vec![];
+        // Push in awkward chunk sizes, so that blocks are both buffered and encoded in place
+        for chunk in data.chunks(97) {
+            assert!(!streaming.is_complete());
+            block_encoders.extend(streaming.push(chunk));
+        }
+        assert!(streaming.is_complete());
+        assert_eq!(streaming.bytes_needed(), 0);
+        assert_eq!(&block_encoders, encoder.get_block_encoders());
+
+        let mut streaming = StreamingEncoder::new(config);
+        assert_eq!(&streaming.push(&data), encoder.get_block_encoders());
+    }
+
+    #[cfg(not(feature = "python"))]
+    #[test]
+    #[should_panic]
+    fn streaming_encoder_rejects_excess_data() {
+        let config = ObjectTransmissionInformation::new(10, 4, 1, 1, 1);
+        let mut streaming = StreamingEncoder::new(config);
+        streaming.push(&[0; 8]);
+        streaming.push(&[0; 3]);
+    }
+
     #[cfg(not(feature = "python"))]
     #[test]
     fn unique_blocks() {
@@ -623,4 +1068,14 @@ mod tests {
         }
         assert_eq!(ids.len(), encoder.get_block_encoders().len());
     }
+
+    #[cfg(not(feature = "python"))]
+    #[test]
+    fn more_blocks_than_symbols() {
+        // `new` accepts this, even though the later blocks start past the end of the data
+        let data = gen_test_data(10);
+        let config = ObjectTransmissionInformation::new(10, 64, 5, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        assert_eq!(encoder.get_block_encoders().len(), 5);
+    }
 }
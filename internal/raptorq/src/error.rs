@@ -0,0 +1,84 @@
+#[cfg(feature = "std")]
+use std::fmt;
+
+#[cfg(not(feature = "std"))]
+use core::fmt;
+
+/// Errors returned by the fallible (`try_*`) variants of the public API.
+///
+/// These cover malformed input, such as a corrupt OTI or packet received from the network,
+/// which the infallible variants treat as a programming error and panic on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The Object Transmission Information describes an invalid or unsupported configuration.
+    InvalidObjectTransmissionInformation(&'static str),
+    /// A Source Block Number does not belong to the object or source block being decoded.
+    SourceBlockNumberOutOfRange(u8),
+    /// An Encoding Symbol ID does not fit in 24 bits, or in the bits of a [`PayloadIdFormat`].
+    ///
+    /// [`PayloadIdFormat`]: crate::PayloadIdFormat
+    EncodingSymbolIdOutOfRange(u32),
+    /// A [`PayloadIdFormat`](crate::PayloadIdFormat) was requested with unsupported bit widths.
+    InvalidPayloadIdFormat(&'static str),
+    /// An [`OtiProfile`](crate::OtiProfile) was requested with unsupported fields.
+    InvalidOtiProfile(&'static str),
+    /// An OTI field differs from the value fixed by an [`OtiProfile`](crate::OtiProfile), or does
+    /// not fit in the bits the profile gives it.
+    OtiProfileMismatch(&'static str),
+    /// A [`RepairPlanner`](crate::RepairPlanner) was given an impossible channel or target.
+    InvalidRepairPlanner(&'static str),
+    /// A decoder checkpoint is corrupt, or was written by an unsupported version.
+    InvalidCheckpoint(&'static str),
+    /// A symbol does not have the length given by the OTI symbol size.
+    WrongSymbolLength { expected: usize, actual: usize },
+    /// An output buffer does not have the length of the data written into it.
+    WrongBufferLength { expected: usize, actual: usize },
+    /// A buffer is shorter than the structure being deserialized from it.
+    TruncatedBuffer { expected: usize, actual: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidObjectTransmissionInformation(reason) => {
+                write!(f, "invalid object transmission information: {reason}")
+            }
+            Error::SourceBlockNumberOutOfRange(sbn) => {
+                write!(f, "source block number {sbn} out of range")
+            }
+            Error::EncodingSymbolIdOutOfRange(esi) => {
+                write!(f, "encoding symbol id {esi} out of range")
+            }
+            Error::InvalidPayloadIdFormat(reason) => {
+                write!(f, "invalid payload id format: {reason}")
+            }
+            Error::InvalidOtiProfile(reason) => {
+                write!(f, "invalid OTI profile: {reason}")
+            }
+            Error::OtiProfileMismatch(field) => {
+                write!(f, "{field} does not fit the OTI profile")
+            }
+            Error::InvalidRepairPlanner(reason) => {
+                write!(f, "invalid repair plan: {reason}")
+            }
+            Error::InvalidCheckpoint(reason) => {
+                write!(f, "invalid decoder checkpoint: {reason}")
+            }
+            Error::WrongSymbolLength { expected, actual } => {
+                write!(f, "wrong symbol length {actual} (expected {expected})")
+            }
+            Error::WrongBufferLength { expected, actual } => {
+                write!(f, "wrong buffer length {actual} (expected {expected})")
+            }
+            Error::TruncatedBuffer { expected, actual } => {
+                write!(
+                    f,
+                    "truncated buffer of {actual} bytes (expected at least {expected})"
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
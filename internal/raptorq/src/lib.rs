@@ -21,6 +21,7 @@ mod base;
 mod constraint_matrix;
 mod decoder;
 mod encoder;
+mod error;
 mod gf2;
 mod graph;
 mod iterators;
@@ -30,28 +31,44 @@ mod octet_matrix;
 mod octets;
 mod operation_vector;
 mod pi_solver;
+#[cfg(feature = "std")]
+mod planner;
 #[cfg(feature = "python")]
 mod python;
 mod rng;
 mod sparse_matrix;
 mod sparse_vec;
 mod symbol;
+mod symbol_matrix;
 mod systematic_constants;
 mod util;
 
 pub use crate::base::EncodingPacket;
 pub use crate::base::ObjectTransmissionInformation;
+pub use crate::base::OtiField;
+pub use crate::base::OtiProfile;
 pub use crate::base::PayloadId;
+pub use crate::base::PayloadIdFormat;
 pub use crate::base::partition;
+pub use crate::decoder::DecodedBlock;
 #[cfg(not(feature = "python"))]
 pub use crate::decoder::Decoder;
 pub use crate::decoder::SourceBlockDecoder;
+pub use crate::decoder::SourceBlockStatus;
 #[cfg(not(feature = "python"))]
 pub use crate::encoder::Encoder;
 pub use crate::encoder::EncoderBuilder;
+pub use crate::encoder::RepairPacketIter;
 pub use crate::encoder::SourceBlockEncoder;
 pub use crate::encoder::SourceBlockEncodingPlan;
+pub use crate::encoder::StreamingEncoder;
+pub use crate::encoder::block_offsets;
 pub use crate::encoder::calculate_block_offsets;
+pub use crate::error::Error;
+#[cfg(feature = "std")]
+pub use crate::planner::RepairPlan;
+#[cfg(feature = "std")]
+pub use crate::planner::RepairPlanner;
 #[cfg(feature = "python")]
 pub use crate::python::Decoder;
 #[cfg(feature = "python")]
@@ -74,3 +91,5 @@ pub use crate::pi_solver::IntermediateSymbolDecoder;
 pub use crate::sparse_matrix::SparseBinaryMatrix;
 #[cfg(feature = "benchmarking")]
 pub use crate::symbol::Symbol;
+#[cfg(feature = "benchmarking")]
+pub use crate::symbol_matrix::SymbolMatrix;
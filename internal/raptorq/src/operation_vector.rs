@@ -5,8 +5,7 @@ use std::vec::Vec;
 use alloc::vec::Vec;
 
 use crate::octet::Octet;
-use crate::symbol::Symbol;
-use crate::util::get_both_indices;
+use crate::symbol_matrix::SymbolMatrix;
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
 
@@ -32,18 +31,16 @@ pub enum SymbolOps {
     },
 }
 
-pub fn perform_op(op: &SymbolOps, symbols: &mut Vec<Symbol>) {
+pub fn perform_op(op: &SymbolOps, symbols: &mut SymbolMatrix) {
     match op {
         SymbolOps::AddAssign { dest, src } => {
-            let (dest, temp) = get_both_indices(symbols, *dest, *src);
-            *dest += temp;
+            symbols.add_assign(*dest, *src);
         }
         SymbolOps::MulAssign { dest, scalar } => {
-            symbols[*dest].mulassign_scalar(scalar);
+            symbols.mulassign_scalar(*dest, scalar);
         }
         SymbolOps::FMA { dest, src, scalar } => {
-            let (dest, temp) = get_both_indices(symbols, *dest, *src);
-            dest.fused_addassign_mul_scalar(temp, scalar);
+            symbols.fused_addassign_mul_scalar(*dest, *src, scalar);
         }
         SymbolOps::Reorder { order } => {
             /* TODO: Reorder is the last step of the algorithm. It should be
@@ -51,10 +48,7 @@ pub fn perform_op(op: &SymbolOps, symbols: &mut Vec<Symbol>) {
              *       creating D (place all rows in correct position before
              *       calculations). This will however force an update on all
              *       row-numbers used in all other "Operations". */
-            let mut temp_symbols: Vec<Option<Symbol>> = symbols.drain(..).map(Some).collect();
-            for row_index in order.iter() {
-                symbols.push(temp_symbols[*row_index].take().unwrap());
-            }
+            symbols.reorder(order);
         }
     }
 }
@@ -67,74 +61,46 @@ mod tests {
 
     use crate::octet::Octet;
     use crate::operation_vector::{SymbolOps, perform_op};
-    use crate::symbol::Symbol;
-
-    #[test]
-    fn test_add() {
-        let rows = 2;
-        let symbol_size = 1316;
-        let mut data: Vec<Symbol> = Vec::with_capacity(rows);
+    use crate::symbol_matrix::SymbolMatrix;
 
-        for _i in 0..rows {
-            let mut symbol_data: Vec<u8> = vec![0; symbol_size];
-            for byte in symbol_data.iter_mut().take(symbol_size) {
+    fn random_symbols(rows: usize, symbol_size: usize) -> SymbolMatrix {
+        let mut data = SymbolMatrix::new(rows, symbol_size);
+        for row in 0..rows {
+            for byte in data.row_mut(row) {
                 *byte = rand::rng().random();
             }
-            let symbol = Symbol::new(symbol_data);
-            data.push(symbol);
         }
+        data
+    }
 
-        let mut data0: Vec<u8> = vec![0; symbol_size];
-        let mut data1: Vec<u8> = vec![0; symbol_size];
-        let mut result: Vec<u8> = vec![0; symbol_size];
-        for (i, ((d0, d1), res)) in data0
-            .iter_mut()
-            .zip(data1.iter_mut())
-            .zip(result.iter_mut())
-            .enumerate()
-        {
-            *d0 = data[0].as_bytes()[i];
-            *d1 = data[1].as_bytes()[i];
-            *res = *d0 ^ *d1;
-        }
-        let mut symbol0 = Symbol::new(data0);
-        let symbol1 = Symbol::new(data1);
+    #[test]
+    fn test_add() {
+        let symbol_size = 1316;
+        let mut data = random_symbols(2, symbol_size);
 
-        symbol0 += &symbol1;
+        let result: Vec<u8> = data
+            .row(0)
+            .iter()
+            .zip(data.row(1).iter())
+            .map(|(d0, d1)| d0 ^ d1)
+            .collect();
 
         perform_op(&SymbolOps::AddAssign { dest: 0, src: 1 }, &mut data);
-        assert_eq!(result, data[0].as_bytes());
+        assert_eq!(result, data.row(0));
     }
 
     #[test]
     fn test_add_mul() {
-        let rows = 2;
         let symbol_size = 1316;
-        let mut data: Vec<Symbol> = Vec::with_capacity(rows);
-
-        for _i in 0..rows {
-            let mut symbol_data: Vec<u8> = vec![0; symbol_size];
-            for byte in symbol_data.iter_mut().take(symbol_size) {
-                *byte = rand::rng().random();
-            }
-            let symbol = Symbol::new(symbol_data);
-            data.push(symbol);
-        }
+        let mut data = random_symbols(2, symbol_size);
 
         let value = 173;
-        let mut data0: Vec<u8> = vec![0; symbol_size];
-        let mut data1: Vec<u8> = vec![0; symbol_size];
-        let mut result: Vec<u8> = vec![0; symbol_size];
-        for (i, ((d0, d1), res)) in data0
-            .iter_mut()
-            .zip(data1.iter_mut())
-            .zip(result.iter_mut())
-            .enumerate()
-        {
-            *d0 = data[0].as_bytes()[i];
-            *d1 = data[1].as_bytes()[i];
-            *res = *d0 ^ (Octet::new(*d1) * Octet::new(value)).byte();
-        }
+        let result: Vec<u8> = data
+            .row(0)
+            .iter()
+            .zip(data.row(1).iter())
+            .map(|(d0, d1)| d0 ^ (Octet::new(*d1) * Octet::new(value)).byte())
+            .collect();
 
         perform_op(
             &SymbolOps::FMA {
@@ -144,31 +110,20 @@ mod tests {
             },
             &mut data,
         );
-        assert_eq!(result, data[0].as_bytes());
+        assert_eq!(result, data.row(0));
     }
 
     #[test]
     fn test_mul() {
-        let rows = 1;
         let symbol_size = 1316;
-        let mut data: Vec<Symbol> = Vec::with_capacity(rows);
-
-        for _i in 0..rows {
-            let mut symbol_data: Vec<u8> = vec![0; symbol_size];
-            for byte in symbol_data.iter_mut().take(symbol_size) {
-                *byte = rand::rng().random();
-            }
-            let symbol = Symbol::new(symbol_data);
-            data.push(symbol);
-        }
+        let mut data = random_symbols(1, symbol_size);
 
         let value = 215;
-        let mut data0: Vec<u8> = vec![0; symbol_size];
-        let mut result: Vec<u8> = vec![0; symbol_size];
-        for (i, (d0, res)) in data0.iter_mut().zip(result.iter_mut()).enumerate() {
-            *d0 = data[0].as_bytes()[i];
-            *res = (Octet::new(*d0) * Octet::new(value)).byte();
-        }
+        let result: Vec<u8> = data
+            .row(0)
+            .iter()
+            .map(|d0| (Octet::new(*d0) * Octet::new(value)).byte())
+            .collect();
 
         perform_op(
             &SymbolOps::MulAssign {
@@ -177,28 +132,25 @@ mod tests {
             },
             &mut data,
         );
-        assert_eq!(result, data[0].as_bytes());
+        assert_eq!(result, data.row(0));
     }
 
     #[test]
     fn test_reorder() {
         let rows = 10;
         let symbol_size = 10;
-        let mut data: Vec<Symbol> = Vec::with_capacity(rows);
+        let mut data = SymbolMatrix::new(rows, symbol_size);
 
         for i in 0..rows {
-            let mut symbol_data: Vec<u8> = vec![0; symbol_size];
-            for byte in symbol_data.iter_mut().take(symbol_size) {
+            for byte in data.row_mut(i) {
                 *byte = i as u8;
             }
-            let symbol = Symbol::new(symbol_data);
-            data.push(symbol);
         }
 
-        assert_eq!(data[0].as_bytes()[0], 0);
-        assert_eq!(data[1].as_bytes()[0], 1);
-        assert_eq!(data[2].as_bytes()[0], 2);
-        assert_eq!(data[9].as_bytes()[0], 9);
+        assert_eq!(data.row(0)[0], 0);
+        assert_eq!(data.row(1)[0], 1);
+        assert_eq!(data.row(2)[0], 2);
+        assert_eq!(data.row(9)[0], 9);
 
         perform_op(
             &SymbolOps::Reorder {
@@ -206,15 +158,15 @@ mod tests {
             },
             &mut data,
         );
-        assert_eq!(data[0].as_bytes()[0], 9);
-        assert_eq!(data[1].as_bytes()[0], 7);
-        assert_eq!(data[2].as_bytes()[0], 5);
-        assert_eq!(data[3].as_bytes()[0], 3);
-        assert_eq!(data[4].as_bytes()[0], 1);
-        assert_eq!(data[5].as_bytes()[0], 8);
-        assert_eq!(data[6].as_bytes()[0], 0);
-        assert_eq!(data[7].as_bytes()[0], 6);
-        assert_eq!(data[8].as_bytes()[0], 2);
-        assert_eq!(data[9].as_bytes()[0], 4);
+        assert_eq!(data.row(0)[0], 9);
+        assert_eq!(data.row(1)[0], 7);
+        assert_eq!(data.row(2)[0], 5);
+        assert_eq!(data.row(3)[0], 3);
+        assert_eq!(data.row(4)[0], 1);
+        assert_eq!(data.row(5)[0], 8);
+        assert_eq!(data.row(6)[0], 0);
+        assert_eq!(data.row(7)[0], 6);
+        assert_eq!(data.row(8)[0], 2);
+        assert_eq!(data.row(9)[0], 4);
     }
 }
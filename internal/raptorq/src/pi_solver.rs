@@ -15,12 +15,11 @@ use crate::octet::Octet;
 use crate::octet_matrix::DenseOctetMatrix;
 use crate::octets::BinaryOctetVec;
 use crate::operation_vector::SymbolOps;
-use crate::symbol::Symbol;
+use crate::symbol_matrix::SymbolMatrix;
 use crate::systematic_constants::num_hdpc_symbols;
 use crate::systematic_constants::num_intermediate_symbols;
 use crate::systematic_constants::num_ldpc_symbols;
 use crate::systematic_constants::num_pi_symbols;
-use crate::util::get_both_indices;
 
 #[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
 enum RowOp {
@@ -431,7 +430,7 @@ pub struct IntermediateSymbolDecoder<T: BinaryMatrix> {
     // since it's not actually needed
     #[cfg(debug_assertions)]
     X: T,
-    D: Vec<Symbol>,
+    D: SymbolMatrix,
     c: Vec<usize>,
     d: Vec<usize>,
     i: usize,
@@ -451,17 +450,17 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
     pub fn new(
         matrix: T,
         hdpc_rows: DenseOctetMatrix,
-        symbols: Vec<Symbol>,
+        symbols: SymbolMatrix,
         num_source_symbols: u32,
     ) -> IntermediateSymbolDecoder<T> {
-        assert!(matrix.width() <= symbols.len());
-        assert_eq!(matrix.height(), symbols.len());
+        assert!(matrix.width() <= symbols.rows());
+        assert_eq!(matrix.height(), symbols.rows());
         let mut c = Vec::with_capacity(matrix.width());
-        let mut d = Vec::with_capacity(symbols.len());
+        let mut d = Vec::with_capacity(symbols.rows());
         for i in 0..matrix.width() {
             c.push(i);
         }
-        for i in 0..symbols.len() {
+        for i in 0..symbols.rows() {
             d.push(i);
         }
 
@@ -519,15 +518,13 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
         for op in self.deferred_D_ops.iter() {
             match op {
                 SymbolOps::AddAssign { dest, src } => {
-                    let (dest, temp) = get_both_indices(&mut self.D, *dest, *src);
-                    *dest += temp;
+                    self.D.add_assign(*dest, *src);
                 }
                 SymbolOps::MulAssign { dest, scalar } => {
-                    self.D[*dest].mulassign_scalar(scalar);
+                    self.D.mulassign_scalar(*dest, scalar);
                 }
                 SymbolOps::FMA { dest, src, scalar } => {
-                    let (dest, temp) = get_both_indices(&mut self.D, *dest, *src);
-                    dest.fused_addassign_mul_scalar(temp, scalar);
+                    self.D.fused_addassign_mul_scalar(*dest, *src, scalar);
                 }
                 SymbolOps::Reorder { order: _order } => {}
             }
@@ -1274,7 +1271,7 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
     }
 
     #[inline(never)]
-    pub fn execute(&mut self) -> (Option<Vec<Symbol>>, Option<Vec<SymbolOps>>) {
+    pub fn execute(&mut self) -> (Option<SymbolMatrix>, Option<Vec<SymbolOps>>) {
         #[cfg(debug_assertions)]
         self.X.disable_column_access_acceleration();
 
@@ -1300,24 +1297,13 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
             index_mapping[self.c[i]] = self.d[i];
         }
 
-        #[allow(non_snake_case)]
-        let mut removable_D: Vec<Option<Symbol>> = self.D.drain(..).map(Some).collect();
-
-        let mut result = Vec::with_capacity(self.L);
-        #[allow(clippy::needless_range_loop)]
-        for i in 0..self.L {
-            // push a None so it can be swapped in
-            removable_D.push(None);
-            result.push(removable_D.swap_remove(index_mapping[i]).unwrap());
-        }
-
-        let mut reorder = Vec::with_capacity(self.L);
-        for i in index_mapping.iter().take(self.L) {
-            reorder.push(*i);
-        }
+        self.D.reorder(&index_mapping);
+        let result = mem::take(&mut self.D);
 
         let mut operation_vector = mem::take(&mut self.deferred_D_ops);
-        operation_vector.push(SymbolOps::Reorder { order: reorder });
+        operation_vector.push(SymbolOps::Reorder {
+            order: index_mapping,
+        });
         return (Some(result), Some(operation_vector));
     }
 }
@@ -1327,9 +1313,9 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
 pub fn fused_inverse_mul_symbols<T: BinaryMatrix>(
     matrix: T,
     hdpc_rows: DenseOctetMatrix,
-    symbols: Vec<Symbol>,
+    symbols: SymbolMatrix,
     num_source_symbols: u32,
-) -> (Option<Vec<Symbol>>, Option<Vec<SymbolOps>>) {
+) -> (Option<SymbolMatrix>, Option<Vec<SymbolOps>>) {
     IntermediateSymbolDecoder::new(matrix, hdpc_rows, symbols, num_source_symbols).execute()
 }
 
@@ -1340,7 +1326,7 @@ mod tests {
     use crate::constraint_matrix::generate_constraint_matrix;
     use crate::matrix::BinaryMatrix;
     use crate::matrix::DenseBinaryMatrix;
-    use crate::symbol::Symbol;
+    use crate::symbol_matrix::SymbolMatrix;
     use crate::systematic_constants::{
         MAX_SOURCE_SYMBOLS_PER_BLOCK, extended_source_block_symbols, num_ldpc_symbols,
         num_lt_symbols,
@@ -1355,7 +1341,7 @@ mod tests {
             let num_symbols = extended_source_block_symbols(elements);
             let indices: Vec<u32> = (0..num_symbols).collect();
             let (a, hdpc) = generate_constraint_matrix::<DenseBinaryMatrix>(num_symbols, &indices);
-            let symbols = vec![Symbol::zero(1usize); a.width()];
+            let symbols = SymbolMatrix::new(a.width(), 1);
             let mut decoder = IntermediateSymbolDecoder::new(a, hdpc, symbols, num_symbols);
             decoder.execute();
             assert!(
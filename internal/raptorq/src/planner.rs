@@ -0,0 +1,278 @@
+use std::vec::Vec;
+
+use crate::base::ObjectTransmissionInformation;
+use crate::base::partition;
+use crate::error::Error;
+use crate::util::int_div_ceil;
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+// Encoding Symbol IDs are 24-bit, see section 3.2
+const MAX_ENCODING_SYMBOLS: u64 = 1 << 24;
+
+/// Chooses the number of repair symbols each source block needs to survive a lossy channel.
+///
+/// The channel drops a fraction `loss_rate` of the packets, either independently or, with a
+/// burst length above one, in runs of that many packets. A block fails to decode if fewer than
+/// K of its packets arrive, and otherwise with the probability section 1 of RFC 6330 gives for
+/// receiving K + h encoding symbols: below 10^-2 for h = 0, 10^-4 for h = 1, 10^-6 for h = 2,
+/// and so on, falling a hundredfold with every additional symbol.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct RepairPlanner {
+    decoder_memory_requirement: u64,
+    max_packet_size: u16,
+    loss_rate: f64,
+    burst_length: u32,
+    target_failure_probability: f64,
+}
+
+/// Encoding parameters for an object, and the repair symbols to send for each of its blocks
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct RepairPlan {
+    config: ObjectTransmissionInformation,
+    repair_symbols: Vec<u32>,
+    failure_probability: f64,
+}
+
+impl RepairPlanner {
+    /// Plans for a channel losing `loss_rate` of the packets, so that the object fails to decode
+    /// with at most `target_failure_probability`. Panics if either is outside [0, 1) or (0, 1).
+    pub fn new(loss_rate: f64, target_failure_probability: f64) -> RepairPlanner {
+        RepairPlanner::try_new(loss_rate, target_failure_probability).unwrap()
+    }
+
+    /// Like [`new`](Self::new), but fails if `loss_rate` is outside [0, 1) or
+    /// `target_failure_probability` is outside (0, 1).
+    pub fn try_new(
+        loss_rate: f64,
+        target_failure_probability: f64,
+    ) -> Result<RepairPlanner, Error> {
+        if !(0.0..1.0).contains(&loss_rate) {
+            return Err(Error::InvalidRepairPlanner(
+                "loss rate must be at least 0 and below 1",
+            ));
+        }
+        if !(target_failure_probability > 0.0 && target_failure_probability < 1.0) {
+            return Err(Error::InvalidRepairPlanner(
+                "target failure probability must be above 0 and below 1",
+            ));
+        }
+        Ok(RepairPlanner {
+            decoder_memory_requirement: 10 * 1024 * 1024,
+            max_packet_size: 1024,
+            loss_rate,
+            burst_length: 1,
+            target_failure_probability,
+        })
+    }
+
+    pub fn set_decoder_memory_requirement(&mut self, bytes: u64) {
+        self.decoder_memory_requirement = bytes;
+    }
+
+    pub fn set_max_packet_size(&mut self, bytes: u16) {
+        self.max_packet_size = bytes;
+    }
+
+    /// Packets are lost in runs of `packets` consecutive packets. Defaults to 1, independent loss.
+    pub fn set_burst_length(&mut self, packets: u32) {
+        assert!(packets > 0);
+        self.burst_length = packets;
+    }
+
+    /// Plans the transfer of a `transfer_length` byte object, with the encoding parameters
+    /// [`EncoderBuilder`](crate::EncoderBuilder) would choose
+    pub fn plan(&self, transfer_length: u64) -> Result<RepairPlan, Error> {
+        let config = ObjectTransmissionInformation::generate_encoding_parameters(
+            transfer_length,
+            self.max_packet_size,
+            self.decoder_memory_requirement,
+        );
+        self.plan_for(config)
+    }
+
+    /// Plans the transfer of an object with the given encoding parameters
+    pub fn plan_for(&self, config: ObjectTransmissionInformation) -> Result<RepairPlan, Error> {
+        let kt = int_div_ceil(config.transfer_length(), config.symbol_size() as u64);
+        let (kl, ks, zl, zs) = partition(kt, config.source_blocks());
+
+        let mut repair_symbols = vec![];
+        let mut success_probability = 1.0;
+        for (source_symbols, blocks) in [(kl, zl), (ks, zs)] {
+            if blocks == 0 {
+                continue;
+            }
+            let repair = self.repair_symbols(source_symbols, config.source_blocks())?;
+            let failure = self.block_failure_probability(source_symbols, repair);
+            repair_symbols.extend((0..blocks).map(|_| repair));
+            success_probability *= (1.0 - failure).powi(blocks as i32);
+        }
+
+        Ok(RepairPlan {
+            config,
+            repair_symbols,
+            failure_probability: 1.0 - success_probability,
+        })
+    }
+
+    /// Smallest number of repair symbols for a block of `source_symbols` symbols, one of
+    /// `source_blocks` blocks which all have to decode for the object to meet the target
+    pub fn repair_symbols(&self, source_symbols: u32, source_blocks: u8) -> Result<u32, Error> {
+        // Split the target evenly, so that the blocks together fail with the target probability
+        let target =
+            -((-self.target_failure_probability).ln_1p() / source_blocks.max(1) as f64).exp_m1();
+        let max_repair = (MAX_ENCODING_SYMBOLS - source_symbols as u64) as u32;
+
+        // Find an upper bound by doubling, then the smallest count meeting the target by bisection
+        let mut low = 0;
+        let mut high = 1;
+        while self.block_failure_probability(source_symbols, high) > target {
+            if high == max_repair {
+                return Err(Error::InvalidRepairPlanner(
+                    "target failure probability needs more encoding symbols than a block has",
+                ));
+            }
+            low = high + 1;
+            high = high.saturating_mul(2).min(max_repair);
+        }
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if self.block_failure_probability(source_symbols, middle) <= target {
+                high = middle;
+            } else {
+                low = middle + 1;
+            }
+        }
+        Ok(high)
+    }
+
+    /// Probability that a block of `source_symbols` symbols fails to decode when sent with
+    /// `repair_symbols` repair symbols
+    pub fn block_failure_probability(&self, source_symbols: u32, repair_symbols: u32) -> f64 {
+        let sent = source_symbols as u64 + repair_symbols as u64;
+        let burst = self.burst_length as u64;
+        // Packets are lost in whole bursts, the last of which may be cut short
+        let bursts = sent.div_ceil(burst);
+        let decoding_failure = |bursts_received: u64| {
+            let received = (bursts_received * burst).min(sent);
+            if received < source_symbols as u64 {
+                1.0
+            } else {
+                let overhead = (received - source_symbols as u64).min(1000) as i32;
+                10f64.powi(-2 * (overhead + 1))
+            }
+        };
+
+        if self.loss_rate == 0.0 {
+            return decoding_failure(bursts);
+        }
+
+        // Sum over the binomial distribution of bursts received, in log space since the
+        // individual terms underflow for large blocks
+        let ln_loss = self.loss_rate.ln();
+        let ln_ratio = (-self.loss_rate).ln_1p() - ln_loss;
+        let mut ln_probability = bursts as f64 * ln_loss;
+        let mut failure = 0.0;
+        for received in 0..=bursts {
+            failure += ln_probability.exp() * decoding_failure(received);
+            ln_probability += ((bursts - received) as f64 / (received + 1) as f64).ln() + ln_ratio;
+        }
+        failure.min(1.0)
+    }
+}
+
+impl RepairPlan {
+    pub fn config(&self) -> ObjectTransmissionInformation {
+        self.config
+    }
+
+    /// Number of repair symbols to send for each source block, indexed by source block number
+    pub fn repair_symbols(&self) -> &[u32] {
+        &self.repair_symbols
+    }
+
+    /// Predicted probability that at least one source block fails to decode
+    pub fn failure_probability(&self) -> f64 {
+        self.failure_probability
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::base::ObjectTransmissionInformation;
+    use crate::planner::RepairPlanner;
+
+    #[test]
+    fn lossless_channel_follows_failure_curve() {
+        let planner = RepairPlanner::new(0.0, 2e-6);
+        assert_eq!(0.01, planner.block_failure_probability(100, 0));
+        assert!((planner.block_failure_probability(100, 1) - 1e-4).abs() < 1e-12);
+        // Below 2 * 10^-6 takes two extra symbols
+        assert_eq!(2, planner.repair_symbols(100, 1).unwrap());
+    }
+
+    #[test]
+    fn repair_symbols_are_smallest_meeting_target() {
+        for (loss_rate, burst_length) in [(0.05, 1), (0.2, 1), (0.1, 8)] {
+            let mut planner = RepairPlanner::new(loss_rate, 1e-4);
+            planner.set_burst_length(burst_length);
+            let repair = planner.repair_symbols(1000, 1).unwrap();
+            assert!(planner.block_failure_probability(1000, repair) <= 1e-4);
+            assert!(planner.block_failure_probability(1000, repair - 1) > 1e-4);
+        }
+    }
+
+    #[test]
+    fn more_loss_needs_more_repair() {
+        let independent = RepairPlanner::new(0.1, 1e-6)
+            .repair_symbols(500, 1)
+            .unwrap();
+        let lossier = RepairPlanner::new(0.2, 1e-6)
+            .repair_symbols(500, 1)
+            .unwrap();
+        let mut bursty = RepairPlanner::new(0.1, 1e-6);
+        bursty.set_burst_length(10);
+        let bursty = bursty.repair_symbols(500, 1).unwrap();
+        // 500 symbols at 10% loss need at least 56 packets more just on average
+        assert!(independent > 56);
+        assert!(lossier > independent);
+        assert!(bursty > independent);
+    }
+
+    #[test]
+    fn plan_covers_every_block() {
+        let config = ObjectTransmissionInformation::new(1_000_000, 1000, 3, 1, 8);
+        let plan = RepairPlanner::new(0.1, 1e-3).plan_for(config).unwrap();
+        assert_eq!(config, plan.config());
+        // Blocks of 334, 333 and 333 symbols
+        let repair = plan.repair_symbols();
+        assert_eq!(3, repair.len());
+        assert!(repair[0] >= repair[1]);
+        assert_eq!(repair[1], repair[2]);
+        assert!(plan.failure_probability() <= 1e-3);
+        assert!(plan.failure_probability() > 0.0);
+    }
+
+    #[test]
+    fn plan_chooses_encoding_parameters() {
+        let mut planner = RepairPlanner::new(0.01, 1e-6);
+        planner.set_max_packet_size(1280);
+        let plan = planner.plan(100_000).unwrap();
+        assert_eq!(
+            ObjectTransmissionInformation::with_defaults(100_000, 1280),
+            plan.config()
+        );
+        assert_eq!(1, plan.repair_symbols().len());
+    }
+
+    #[test]
+    fn invalid_parameters_are_rejected() {
+        assert!(RepairPlanner::try_new(1.0, 1e-6).is_err());
+        assert!(RepairPlanner::try_new(-0.1, 1e-6).is_err());
+        assert!(RepairPlanner::try_new(0.1, 0.0).is_err());
+        assert!(RepairPlanner::try_new(0.1, f64::NAN).is_err());
+        assert!(RepairPlanner::try_new(f64::NAN, 1e-6).is_err());
+    }
+}
@@ -8,7 +8,7 @@ use pyo3::types::*;
 
 #[pyclass]
 pub struct Encoder {
-    encoder: EncoderNative,
+    encoder: EncoderNative<'static>,
 }
 
 #[pymethods]
@@ -18,7 +18,8 @@ impl Encoder {
         data: Bound<'_, PyBytes>,
         maximum_transmission_unit: u16,
     ) -> PyResult<Encoder> {
-        let encoder = EncoderNative::with_defaults(data.as_bytes(), maximum_transmission_unit);
+        let encoder =
+            EncoderNative::with_defaults(data.as_bytes(), maximum_transmission_unit).into_owned();
         Ok(Encoder { encoder })
     }
 
@@ -68,6 +69,24 @@ impl Decoder {
             .decode(EncodingPacket::deserialize(packet.as_bytes()));
         Ok(result.map(|data| PyBytes::new(py, &data).into()))
     }
+
+    /// Returns `(sbn, offset, data)` as soon as a source block has been decoded
+    pub fn decode_block(
+        &mut self,
+        py: Python<'_>,
+        packet: Bound<'_, PyBytes>,
+    ) -> PyResult<Option<(u8, u64, Py<PyBytes>)>> {
+        let result = self
+            .decoder
+            .decode_block(EncodingPacket::deserialize(packet.as_bytes()));
+        Ok(result.map(|block| {
+            (
+                block.sbn,
+                block.offset,
+                PyBytes::new(py, &block.data).into(),
+            )
+        }))
+    }
 }
 
 #[pymodule]
@@ -7,9 +7,12 @@ use core::ops::AddAssign;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
+#[cfg(feature = "benchmarking")]
 use crate::octet::Octet;
 use crate::octets::add_assign;
+#[cfg(feature = "benchmarking")]
 use crate::octets::fused_addassign_mul_scalar;
+#[cfg(feature = "benchmarking")]
 use crate::octets::mulassign_scalar;
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
@@ -27,6 +30,7 @@ impl Symbol {
     }
 
     /// Initialize a zeroed symbol, with given size.
+    #[cfg(feature = "benchmarking")]
     pub fn zero<T>(size: T) -> Symbol
     where
         T: Into<usize>,
@@ -47,14 +51,17 @@ impl Symbol {
     }
 
     /// Consume a symbol into a vector of bytes.
+    #[cfg(any(test, feature = "benchmarking"))]
     pub fn into_bytes(self) -> Vec<u8> {
         self.value
     }
 
+    #[cfg(feature = "benchmarking")]
     pub fn mulassign_scalar(&mut self, scalar: &Octet) {
         mulassign_scalar(&mut self.value, scalar);
     }
 
+    #[cfg(feature = "benchmarking")]
     pub fn fused_addassign_mul_scalar(&mut self, other: &Symbol, scalar: &Octet) {
         fused_addassign_mul_scalar(&mut self.value, &other.value, scalar);
     }
@@ -0,0 +1,270 @@
+#[cfg(feature = "std")]
+use std::{slice, vec::Vec};
+
+#[cfg(not(feature = "std"))]
+use core::slice;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use crate::octet::Octet;
+use crate::octets::add_assign;
+use crate::octets::fused_addassign_mul_scalar;
+use crate::octets::mulassign_scalar;
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+// Every row of symbols at least this long starts on a multiple of this many bytes, the width of
+// the widest vector registers used by the octet kernels
+const ROW_ALIGNMENT: usize = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+#[repr(C, align(32))]
+struct AlignedBytes([u8; ROW_ALIGNMENT]);
+
+/// Symbols of equal size, stored as the rows of a single aligned allocation.
+///
+/// Keeping the symbols of a block next to each other, rather than each in an allocation of its
+/// own, keeps the row operations of encoding and decoding within a few pages of memory.
+#[derive(Clone, Debug, Default, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct SymbolMatrix {
+    rows: usize,
+    symbol_size: usize,
+    // Bytes from the start of one row to the start of the next: the symbol size, rounded up to
+    // ROW_ALIGNMENT unless the symbol is shorter than that. Shorter symbols are packed back to back,
+    // as padding them would multiply the memory of a block for rows that take a single vector
+    // operation either way. The bytes after the end of a symbol up to the next row (or the end of
+    // the storage) are always zero.
+    stride: usize,
+    storage: Vec<AlignedBytes>,
+}
+
+impl SymbolMatrix {
+    /// Creates `rows` zeroed symbols of `symbol_size` bytes.
+    pub fn new(rows: usize, symbol_size: usize) -> SymbolMatrix {
+        let stride = if symbol_size >= ROW_ALIGNMENT {
+            symbol_size.div_ceil(ROW_ALIGNMENT) * ROW_ALIGNMENT
+        } else {
+            symbol_size
+        };
+        SymbolMatrix {
+            rows,
+            symbol_size,
+            stride,
+            storage: vec![
+                AlignedBytes([0; ROW_ALIGNMENT]);
+                (rows * stride).div_ceil(ROW_ALIGNMENT)
+            ],
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn symbol_size(&self) -> usize {
+        self.symbol_size
+    }
+
+    fn bytes(&self) -> &[u8] {
+        // Safe because AlignedBytes is a byte array without padding, so the storage is
+        // initialized memory of exactly storage.len() * ROW_ALIGNMENT bytes
+        unsafe {
+            slice::from_raw_parts(
+                self.storage.as_ptr() as *const u8,
+                self.storage.len() * ROW_ALIGNMENT,
+            )
+        }
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        // Safe for the same reason as bytes(), and any byte pattern is a valid AlignedBytes
+        unsafe {
+            slice::from_raw_parts_mut(
+                self.storage.as_mut_ptr() as *mut u8,
+                self.storage.len() * ROW_ALIGNMENT,
+            )
+        }
+    }
+
+    pub fn row(&self, row: usize) -> &[u8] {
+        assert!(row < self.rows);
+        let start = row * self.stride;
+        &self.bytes()[start..start + self.symbol_size]
+    }
+
+    pub fn row_mut(&mut self, row: usize) -> &mut [u8] {
+        assert!(row < self.rows);
+        let start = row * self.stride;
+        let symbol_size = self.symbol_size;
+        &mut self.bytes_mut()[start..start + symbol_size]
+    }
+
+    // Borrows row `dest` mutably along with a different row `src`
+    fn dest_and_src(&mut self, dest: usize, src: usize) -> (&mut [u8], &[u8]) {
+        assert_ne!(dest, src);
+        assert!(dest < self.rows && src < self.rows);
+        let (stride, symbol_size) = (self.stride, self.symbol_size);
+        let bytes = self.bytes_mut();
+        if dest < src {
+            let (first, last) = bytes.split_at_mut(src * stride);
+            (
+                &mut first[dest * stride..dest * stride + symbol_size],
+                &last[..symbol_size],
+            )
+        } else {
+            let (first, last) = bytes.split_at_mut(dest * stride);
+            (
+                &mut last[..symbol_size],
+                &first[src * stride..src * stride + symbol_size],
+            )
+        }
+    }
+
+    /// Adds row `src` to row `dest`.
+    pub fn add_assign(&mut self, dest: usize, src: usize) {
+        let (dest, src) = self.dest_and_src(dest, src);
+        add_assign(dest, src);
+    }
+
+    /// Multiplies row `dest` by `scalar`.
+    pub fn mulassign_scalar(&mut self, dest: usize, scalar: &Octet) {
+        mulassign_scalar(self.row_mut(dest), scalar);
+    }
+
+    /// Adds row `src`, multiplied by `scalar`, to row `dest`.
+    pub fn fused_addassign_mul_scalar(&mut self, dest: usize, src: usize, scalar: &Octet) {
+        let (dest, src) = self.dest_and_src(dest, src);
+        fused_addassign_mul_scalar(dest, src, scalar);
+    }
+
+    /// Rearranges the rows, so that row `i` becomes the row that was at `order[i]`. Rows that do
+    /// not appear in `order` are dropped, leaving `order.len()` rows.
+    pub fn reorder(&mut self, order: &[usize]) {
+        // Complete the order with the dropped rows, so that it permutes every row
+        let mut used = vec![false; self.rows];
+        for &row in order {
+            assert!(!used[row], "row {row} appears twice");
+            used[row] = true;
+        }
+        let mut permutation = order.to_vec();
+        permutation.extend((0..self.rows).filter(|&row| !used[row]));
+
+        // Follow each cycle of the permutation, so that rows are moved in place with a single row
+        // of temporary storage
+        let stride = self.stride;
+        let mut temp = vec![0; stride];
+        let mut placed = vec![false; self.rows];
+        for start in 0..self.rows {
+            if placed[start] {
+                continue;
+            }
+            let bytes = self.bytes_mut();
+            temp.copy_from_slice(&bytes[start * stride..(start + 1) * stride]);
+            let mut row = start;
+            loop {
+                placed[row] = true;
+                let next = permutation[row];
+                if next == start {
+                    bytes[row * stride..(row + 1) * stride].copy_from_slice(&temp);
+                    break;
+                }
+                bytes.copy_within(next * stride..(next + 1) * stride, row * stride);
+                row = next;
+            }
+        }
+
+        self.rows = order.len();
+        let end = self.rows * self.stride;
+        self.storage.truncate(end.div_ceil(ROW_ALIGNMENT));
+        // Packed rows may leave part of a dropped row in the last chunk of the storage
+        self.bytes_mut()[end..].fill(0);
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use std::vec::Vec;
+
+    use crate::octet::Octet;
+    use crate::symbol_matrix::SymbolMatrix;
+
+    fn random_matrix(rows: usize, symbol_size: usize) -> SymbolMatrix {
+        let mut matrix = SymbolMatrix::new(rows, symbol_size);
+        for row in 0..rows {
+            for byte in matrix.row_mut(row) {
+                *byte = rand::rng().random();
+            }
+        }
+        matrix
+    }
+
+    #[test]
+    fn rows_are_aligned() {
+        let matrix = SymbolMatrix::new(5, 41);
+        for row in 0..5 {
+            assert_eq!(matrix.row(row).len(), 41);
+            assert_eq!(matrix.row(row).as_ptr() as usize % 32, 0);
+        }
+    }
+
+    #[test]
+    fn row_operations() {
+        let mut matrix = random_matrix(3, 1316);
+        let rows: Vec<Vec<u8>> = (0..3).map(|row| matrix.row(row).to_vec()).collect();
+        let scalar = Octet::new(173);
+
+        matrix.add_assign(2, 0);
+        matrix.fused_addassign_mul_scalar(0, 1, &scalar);
+        matrix.mulassign_scalar(1, &scalar);
+        for i in 0..1316 {
+            assert_eq!(matrix.row(2)[i], rows[2][i] ^ rows[0][i]);
+            let product = (Octet::new(rows[1][i]) * scalar.clone()).byte();
+            assert_eq!(matrix.row(0)[i], rows[0][i] ^ product);
+            assert_eq!(matrix.row(1)[i], product);
+        }
+    }
+
+    #[test]
+    fn reorder_permutes_and_drops_rows() {
+        let mut matrix = random_matrix(10, 37);
+        let rows: Vec<Vec<u8>> = (0..10).map(|row| matrix.row(row).to_vec()).collect();
+
+        let order = [9, 7, 5, 3, 1, 8, 0, 6];
+        matrix.reorder(&order);
+        assert_eq!(matrix.rows(), order.len());
+        for (row, &from) in order.iter().enumerate() {
+            assert_eq!(matrix.row(row), &rows[from][..]);
+        }
+        // The padding after each symbol is still zero, so equal rows compare equal
+        let mut expected = SymbolMatrix::new(order.len(), 37);
+        for (row, &from) in order.iter().enumerate() {
+            expected.row_mut(row).copy_from_slice(&rows[from]);
+        }
+        assert_eq!(matrix, expected);
+    }
+
+    #[test]
+    fn short_rows_are_packed() {
+        let mut matrix = random_matrix(10, 8);
+        assert_eq!(matrix.storage.len(), 3);
+        let rows: Vec<Vec<u8>> = (0..10).map(|row| matrix.row(row).to_vec()).collect();
+
+        matrix.add_assign(3, 4);
+        assert_eq!(matrix.row(4), &rows[4][..]);
+        matrix.add_assign(3, 4);
+        // Dropping rows leaves 56 bytes, part of the second 32 byte chunk
+        let order = [6, 4, 2, 0, 9, 7, 5];
+        matrix.reorder(&order);
+        assert_eq!(matrix.storage.len(), 2);
+        let mut expected = SymbolMatrix::new(order.len(), 8);
+        for (row, &from) in order.iter().enumerate() {
+            expected.row_mut(row).copy_from_slice(&rows[from]);
+        }
+        assert_eq!(matrix, expected);
+    }
+}
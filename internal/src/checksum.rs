@@ -0,0 +1,112 @@
+use clap::ValueEnum;
+use sha2::{Digest, Sha256};
+use xxhash_rust::xxh64::xxh64;
+
+// Number of SHA-256 digest bytes kept per packet
+const SHA256_TRUNCATED_SIZE: usize = 16;
+
+/// Integrity check appended to every serialized encoding packet (`--checksum`)
+///
+/// The checksum covers everything before it in the packet (the OTI if embedded, the PayloadId
+/// and the symbol) and is appended after it, so it makes up the last `size()` bytes of every
+/// packet in the [`PacketLayout`](crate::packet::PacketLayout). Integers are written big-endian,
+/// like the PayloadId.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Checksum {
+    /// CRC-32C (Castagnoli), 4 bytes
+    Crc32c,
+    /// xxHash64 with seed 0, 8 bytes
+    Xxhash64,
+    /// SHA-256 truncated to its first 16 bytes
+    Sha256,
+    /// BLAKE3, 32 bytes
+    Blake3,
+}
+
+impl Checksum {
+    pub fn size(self) -> usize {
+        match self {
+            Checksum::Crc32c => 4,
+            Checksum::Xxhash64 => 8,
+            Checksum::Sha256 => SHA256_TRUNCATED_SIZE,
+            Checksum::Blake3 => blake3::OUT_LEN,
+        }
+    }
+
+    fn compute(self, packet: &[u8], output: &mut [u8]) {
+        match self {
+            Checksum::Crc32c => output.copy_from_slice(&crc32c::crc32c(packet).to_be_bytes()),
+            Checksum::Xxhash64 => output.copy_from_slice(&xxh64(packet, 0).to_be_bytes()),
+            Checksum::Sha256 => output.copy_from_slice(&Sha256::digest(packet)[..SHA256_TRUNCATED_SIZE]),
+            Checksum::Blake3 => output.copy_from_slice(blake3::hash(packet).as_bytes()),
+        }
+    }
+
+    /// Appends the checksum of the `length`-byte packet at the start of `buffer`, returning the
+    /// new length. `buffer` must have room for the checksum.
+    pub fn seal(self, buffer: &mut [u8], length: usize) -> usize {
+        let (packet, rest) = buffer.split_at_mut(length);
+        self.compute(packet, &mut rest[..self.size()]);
+        length + self.size()
+    }
+
+    /// Returns the packet without its checksum if the checksum matches, `None` otherwise.
+    pub fn verify(self, sealed: &[u8]) -> Option<&[u8]> {
+        let (packet, checksum) = sealed.split_at(sealed.len().checked_sub(self.size())?);
+        let mut expected = [0u8; blake3::OUT_LEN];
+        self.compute(packet, &mut expected[..self.size()]);
+        (checksum == &expected[..self.size()]).then_some(packet)
+    }
+}
+
+/// Size of the checksum appended to each packet, if any
+pub fn checksum_size(checksum: Option<Checksum>) -> usize {
+    checksum.map_or(0, Checksum::size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sealed(checksum: Checksum, packet: &[u8]) -> Vec<u8> {
+        let mut buffer = packet.to_vec();
+        buffer.resize(packet.len() + checksum.size(), 0);
+        assert_eq!(checksum.seal(&mut buffer, packet.len()), buffer.len());
+        buffer
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    #[test]
+    fn known_answers() {
+        let vectors = [
+            (Checksum::Crc32c, &b"123456789"[..], "e3069283"),
+            (Checksum::Xxhash64, &b""[..], "ef46db3751d8e999"),
+            (Checksum::Sha256, &b"abc"[..], "ba7816bf8f01cfea414140de5dae2223"),
+            (Checksum::Blake3, &b""[..], "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"),
+        ];
+        for (checksum, packet, expected) in vectors {
+            assert_eq!(hex(&sealed(checksum, packet)[packet.len()..]), expected, "{:?}", checksum);
+        }
+    }
+
+    #[test]
+    fn corrupted_packets_are_rejected() {
+        let packet: Vec<u8> = (0..68).map(|i| i as u8).collect();
+        for checksum in [Checksum::Crc32c, Checksum::Xxhash64, Checksum::Sha256, Checksum::Blake3] {
+            let sealed = sealed(checksum, &packet);
+            assert_eq!(checksum.verify(&sealed), Some(&packet[..]));
+
+            // A flipped bit anywhere, in the packet or in the checksum itself
+            for position in [0, packet.len() - 1, sealed.len() - 1] {
+                let mut corrupted = sealed.clone();
+                corrupted[position] ^= 0x10;
+                assert_eq!(checksum.verify(&corrupted), None, "{:?}", checksum);
+            }
+            assert_eq!(checksum.verify(&sealed[..sealed.len() - 1]), None);
+            assert_eq!(checksum.verify(&sealed[..checksum.size() - 1]), None);
+        }
+    }
+}
@@ -0,0 +1,384 @@
+use std::io;
+use clap::ValueEnum;
+
+use crate::input::{ReadOutcome, TimedReader};
+
+// Longest packet accepted before the packet size is known (the first packet with --oti-per-packet):
+// a full OTI, an RFC 6330 PayloadId, the largest symbol and the largest checksum
+const MAX_PACKET_SIZE: usize = 12 + 4 + u16::MAX as usize + 32;
+
+// A varint length prefix of MAX_PACKET_SIZE takes 3 bytes, so anything longer is garbage
+const MAX_VARINT_SIZE: usize = 3;
+
+/// How packets are delimited on the wire (`--framing`)
+///
+/// The OTI header, if any, is written unframed ahead of the first frame. With a self-delimiting
+/// framing, the decoder resynchronizes after garbage by skipping bytes (or, with COBS, whole
+/// frames) until a frame of the expected packet size comes along. Integers are written
+/// big-endian, like the PayloadId.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Framing {
+    /// Packets back to back, delimited only by their size
+    Fixed,
+    /// Each packet prefixed with its length as an unsigned LEB128 varint
+    VarintLength,
+    /// Each packet prefixed with its length as a 16-bit integer
+    U16Length,
+    /// Each packet COBS-encoded and terminated by a zero byte
+    Cobs,
+    /// Each packet in a datagram of its own, which the input hands over whole (`recv` only)
+    #[value(skip)]
+    Datagram,
+}
+
+enum LengthPrefix {
+    Incomplete,
+    Length(usize),
+    Invalid,
+}
+
+impl Framing {
+    /// Value of the `--framing` flag selecting this framing
+    pub fn name(self) -> &'static str {
+        match self {
+            Framing::Fixed => "fixed",
+            Framing::VarintLength => "varint-length",
+            Framing::U16Length => "u16-length",
+            Framing::Cobs => "cobs",
+            Framing::Datagram => "datagram",
+        }
+    }
+
+    /// Largest frame carrying a `packet_size`-byte packet
+    pub fn max_frame_size(self, packet_size: usize) -> usize {
+        match self {
+            Framing::Fixed | Framing::Datagram => packet_size,
+            Framing::VarintLength => varint_size(packet_size) + packet_size,
+            Framing::U16Length => 2 + packet_size,
+            Framing::Cobs => packet_size + packet_size / 254 + 2,
+        }
+    }
+
+    /// Largest packet that can be framed
+    pub fn max_packet_size(self) -> usize {
+        match self {
+            Framing::U16Length => u16::MAX as usize,
+            _ => usize::MAX,
+        }
+    }
+
+    /// Appends `packet` to `frame`, framed
+    pub fn write_frame(self, packet: &[u8], frame: &mut Vec<u8>) {
+        match self {
+            Framing::Fixed | Framing::Datagram => {}
+            Framing::VarintLength => {
+                let mut length = packet.len();
+                while length >= 0x80 {
+                    frame.push(length as u8 | 0x80);
+                    length >>= 7;
+                }
+                frame.push(length as u8);
+            }
+            Framing::U16Length => frame.extend_from_slice(&(packet.len() as u16).to_be_bytes()),
+            Framing::Cobs => return cobs_encode(packet, frame),
+        }
+        frame.extend_from_slice(packet);
+    }
+
+    /// Reads the next `packet_size`-byte packet into `packet`, skipping anything that is not a
+    /// frame of that size
+    pub fn read_packet(self, reader: &mut TimedReader, packet_size: usize, packet: &mut Vec<u8>) -> io::Result<ReadOutcome> {
+        if self == Framing::Fixed {
+            packet.resize(packet_size, 0);
+            return reader.read_exact(packet);
+        }
+        self.read_frame(reader, Some(packet_size), |_| true, packet)
+    }
+
+    /// Reads the next packet of unknown size that `accept` approves of into `packet`, skipping
+    /// anything else. Only self-delimiting framings can do this.
+    pub fn read_first_packet(self, reader: &mut TimedReader, accept: impl Fn(&[u8]) -> bool, packet: &mut Vec<u8>) -> io::Result<ReadOutcome> {
+        assert_ne!(self, Framing::Fixed, "fixed framing needs the packet size");
+        self.read_frame(reader, None, accept, packet)
+    }
+
+    fn read_frame(self, reader: &mut TimedReader, packet_size: Option<usize>, accept: impl Fn(&[u8]) -> bool, packet: &mut Vec<u8>) -> io::Result<ReadOutcome> {
+        let plausible = |length: usize| match packet_size {
+            Some(packet_size) => length == packet_size,
+            None => length > 0 && length <= MAX_PACKET_SIZE,
+        };
+
+        if self == Framing::Datagram {
+            loop {
+                match reader.read_chunk(packet)? {
+                    ReadOutcome::Filled => {}
+                    outcome => return Ok(outcome),
+                }
+                if plausible(packet.len()) && accept(packet) {
+                    return Ok(ReadOutcome::Filled);
+                }
+                log_info!("  → Skipped a malformed {} byte datagram", packet.len());
+            }
+        }
+
+        if self == Framing::Cobs {
+            let max_frame_size = self.max_frame_size(MAX_PACKET_SIZE);
+            loop {
+                // Search the buffered bytes for the delimiter, waiting for more only once all of
+                // them have been searched
+                let mut discarded = 0;
+                let mut searched = 0;
+                let frame_size = loop {
+                    let buffered = reader.buffered();
+                    if let Some(i) = reader.peek(buffered)[searched..].iter().position(|&byte| byte == 0) {
+                        break searched + i;
+                    }
+                    searched = buffered;
+                    // Keep consuming an overlong frame up to its delimiter, without buffering it
+                    if searched > max_frame_size {
+                        reader.consume(searched);
+                        discarded += searched;
+                        searched = 0;
+                    }
+                    match reader.fill(searched + 1)? {
+                        ReadOutcome::Filled => {}
+                        outcome => return Ok(outcome),
+                    }
+                };
+
+                let well_formed = discarded == 0
+                    && frame_size <= max_frame_size
+                    && cobs_decode(reader.peek(frame_size), packet);
+                reader.consume(frame_size + 1);
+                if well_formed && plausible(packet.len()) && accept(packet) {
+                    return Ok(ReadOutcome::Filled);
+                }
+                log_info!("  → Skipped a malformed {} byte frame", discarded + frame_size);
+            }
+        }
+
+        // Length-prefixed frames are looked at in place, so that when one turns out to be garbage
+        // the search for the next frame can resume just one byte further on
+        let mut skipped = 0;
+        loop {
+            let mut prefix_size = 1;
+            let length = loop {
+                match reader.fill(prefix_size)? {
+                    ReadOutcome::Filled => {}
+                    outcome => return Ok(outcome),
+                }
+                match self.length_prefix(reader.peek(prefix_size)) {
+                    LengthPrefix::Incomplete => prefix_size += 1,
+                    LengthPrefix::Length(length) => break Some(length),
+                    LengthPrefix::Invalid => break None,
+                }
+            };
+
+            if let Some(length) = length.filter(|&length| plausible(length)) {
+                let frame_size = prefix_size + length;
+                match reader.fill(frame_size)? {
+                    ReadOutcome::Filled => {
+                        let frame = &reader.peek(frame_size)[prefix_size..];
+                        if accept(frame) {
+                            packet.clear();
+                            packet.extend_from_slice(frame);
+                            reader.consume(frame_size);
+                            if skipped > 0 {
+                                log_info!("  → Skipped {} bytes to resynchronize with the next frame", skipped);
+                            }
+                            return Ok(ReadOutcome::Filled);
+                        }
+                    }
+                    // A frame running past the end of the stream was never one to begin with
+                    ReadOutcome::EndOfStream => {}
+                    outcome => return Ok(outcome),
+                }
+            }
+
+            // Not the start of a frame: slide forward by one byte
+            reader.consume(1);
+            skipped += 1;
+        }
+    }
+
+    fn length_prefix(self, prefix: &[u8]) -> LengthPrefix {
+        match self {
+            Framing::U16Length => match prefix {
+                [high, low] => LengthPrefix::Length(u16::from_be_bytes([*high, *low]) as usize),
+                _ => LengthPrefix::Incomplete,
+            },
+            _ => {
+                let mut length = 0;
+                for (i, byte) in prefix.iter().enumerate() {
+                    length |= ((byte & 0x7f) as usize) << (7 * i);
+                    if byte & 0x80 == 0 {
+                        return LengthPrefix::Length(length);
+                    }
+                }
+                if prefix.len() < MAX_VARINT_SIZE { LengthPrefix::Incomplete } else { LengthPrefix::Invalid }
+            }
+        }
+    }
+}
+
+fn varint_size(value: usize) -> usize {
+    let bits = usize::BITS - value.leading_zeros();
+    (bits.max(1) as usize).div_ceil(7)
+}
+
+/// Consistent Overhead Byte Stuffing: every run of up to 254 non-zero bytes is prefixed with its
+/// length plus one, which replaces the zero byte that ends it, so the frame itself contains no
+/// zeros and can be terminated by one
+fn cobs_encode(packet: &[u8], frame: &mut Vec<u8>) {
+    let mut code_index = frame.len();
+    frame.push(0);
+    let mut code = 1u8;
+    for &byte in packet {
+        if byte != 0 {
+            frame.push(byte);
+            code += 1;
+        }
+        if byte == 0 || code == 0xff {
+            frame[code_index] = code;
+            code_index = frame.len();
+            frame.push(0);
+            code = 1;
+        }
+    }
+    frame[code_index] = code;
+    frame.push(0);
+}
+
+/// Decodes a COBS frame without its terminating zero, returning whether it was well-formed
+fn cobs_decode(frame: &[u8], packet: &mut Vec<u8>) -> bool {
+    packet.clear();
+    let mut i = 0;
+    while i < frame.len() {
+        let code = frame[i] as usize;
+        if code == 0 || i + code > frame.len() {
+            return false;
+        }
+        packet.extend_from_slice(&frame[i + 1..i + code]);
+        i += code;
+        if code < 0xff && i < frame.len() {
+            packet.push(0);
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn reader(stream: Vec<u8>) -> TimedReader {
+        TimedReader::spawn(Cursor::new(stream), None)
+    }
+
+    fn packet(length: usize) -> Vec<u8> {
+        (0..length).map(|i| (i * 7) as u8).collect()
+    }
+
+    fn cobs(packet: &[u8]) -> Vec<u8> {
+        let mut frame = vec![];
+        Framing::Cobs.write_frame(packet, &mut frame);
+        frame
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let packets = [packet(32), vec![0; 32], vec![0xff; 32]];
+        for framing in [Framing::Fixed, Framing::VarintLength, Framing::U16Length, Framing::Cobs] {
+            let mut stream = vec![];
+            for packet in &packets {
+                framing.write_frame(packet, &mut stream);
+            }
+            let mut reader = reader(stream);
+            let mut read = vec![];
+            for packet in &packets {
+                assert!(matches!(framing.read_packet(&mut reader, 32, &mut read).unwrap(), ReadOutcome::Filled));
+                assert_eq!(&read, packet, "{}", framing.name());
+            }
+            assert!(matches!(framing.read_packet(&mut reader, 32, &mut read).unwrap(), ReadOutcome::EndOfStream));
+        }
+    }
+
+    #[test]
+    fn resynchronizes_after_garbage() {
+        // Garbage before and between the frames, which no length prefix makes look like a frame
+        let garbage = [0xff, 0x13, 0x07, 0x00];
+        let packets = [packet(32), packet(32).into_iter().rev().collect()];
+        for framing in [Framing::VarintLength, Framing::U16Length, Framing::Cobs] {
+            let mut stream = garbage.to_vec();
+            framing.write_frame(&packets[0], &mut stream);
+            stream.extend_from_slice(&garbage);
+            framing.write_frame(&packets[1], &mut stream);
+
+            let mut reader = reader(stream);
+            let mut read = vec![];
+            for packet in &packets {
+                assert!(matches!(framing.read_packet(&mut reader, 32, &mut read).unwrap(), ReadOutcome::Filled));
+                assert_eq!(&read, packet, "{}", framing.name());
+            }
+        }
+    }
+
+    #[test]
+    fn cobs_handles_zero_and_long_runs() {
+        assert_eq!(cobs(&[]), [0x01, 0x00]);
+        assert_eq!(cobs(&[0]), [0x01, 0x01, 0x00]);
+        assert_eq!(cobs(&[0, 0]), [0x01, 0x01, 0x01, 0x00]);
+
+        // A run of 254 non-zero bytes fills a whole block, which is not followed by a zero
+        let run = vec![0x42; 254];
+        let mut expected = vec![0xff];
+        expected.extend_from_slice(&run);
+        expected.extend_from_slice(&[0x01, 0x00]);
+        assert_eq!(cobs(&run), expected);
+
+        let run = vec![0x42; 255];
+        let mut expected = vec![0xff];
+        expected.extend_from_slice(&run[..254]);
+        expected.extend_from_slice(&[0x02, 0x42, 0x00]);
+        assert_eq!(cobs(&run), expected);
+
+        for packet in [vec![], vec![0], vec![0x42; 254], vec![0x42; 255], [vec![0x42; 254], vec![0]].concat()] {
+            let frame = cobs(&packet);
+            assert_eq!(frame.iter().position(|&byte| byte == 0), Some(frame.len() - 1));
+            let mut decoded = vec![];
+            assert!(cobs_decode(&frame[..frame.len() - 1], &mut decoded));
+            assert_eq!(decoded, packet);
+        }
+
+        // A block claiming more bytes than the frame holds
+        assert!(!cobs_decode(&[0x05, 0x01], &mut vec![]));
+
+        // A frame longer than any packet is skipped up to its delimiter
+        let mut stream = vec![0x42; 2 * MAX_PACKET_SIZE];
+        stream.push(0);
+        stream.extend(cobs(&packet(32)));
+        let mut read = vec![];
+        assert!(matches!(Framing::Cobs.read_packet(&mut reader(stream), 32, &mut read).unwrap(), ReadOutcome::Filled));
+        assert_eq!(read, packet(32));
+    }
+
+    #[test]
+    fn varint_prefix_is_at_most_three_bytes() {
+        let framing = Framing::VarintLength;
+        assert!(matches!(framing.length_prefix(&[0xff, 0xff, 0x03]), LengthPrefix::Length(0xffff)));
+        assert!(matches!(framing.length_prefix(&[0x80, 0x80]), LengthPrefix::Incomplete));
+        assert!(matches!(framing.length_prefix(&[0x80, 0x80, 0x80]), LengthPrefix::Invalid));
+
+        // The longest packet takes the longest prefix, even before the packet size is known
+        let packet = packet(MAX_PACKET_SIZE);
+        let mut stream = vec![];
+        framing.write_frame(&packet, &mut stream);
+        assert_eq!(stream.len(), MAX_VARINT_SIZE + MAX_PACKET_SIZE);
+        assert_eq!(framing.max_frame_size(MAX_PACKET_SIZE), stream.len());
+        let mut read = vec![];
+        assert!(matches!(framing.read_first_packet(&mut reader(stream), |_| true, &mut read).unwrap(), ReadOutcome::Filled));
+        assert_eq!(read, packet);
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+// Size of each chunk read by the background thread
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// Number of chunks that may be buffered ahead of the consumer
+const CHUNK_BACKLOG: usize = 16;
+
+pub enum ReadOutcome {
+    Filled,
+    EndOfStream,
+    IdleTimeout,
+}
+
+/// Reads from a blocking source (e.g. stdin) on a background thread
+///
+/// Standard input has no portable read timeout, so reads are performed on a separate thread and
+/// handed over through a channel. This lets the decoder give up after an idle timeout instead of
+/// blocking forever on a sender that has gone quiet.
+pub struct TimedReader {
+    chunks: Receiver<io::Result<Vec<u8>>>,
+    buffer: VecDeque<u8>,
+    // Stream positions at which the chunks still (partly) buffered end
+    chunk_ends: VecDeque<u64>,
+    idle_timeout: Option<Duration>,
+    ended: bool,
+    pending_error: Option<io::Error>,
+    consumed: u64,
+}
+
+impl TimedReader {
+    pub fn spawn<R: Read + Send + 'static>(mut source: R, idle_timeout: Option<Duration>) -> TimedReader {
+        let (sender, chunks) = mpsc::sync_channel(CHUNK_BACKLOG);
+
+        thread::spawn(move || {
+            let mut chunk = vec![0u8; CHUNK_SIZE];
+            loop {
+                let result = match source.read(&mut chunk) {
+                    Ok(n) => Ok(chunk[..n].to_vec()),
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => Err(e),
+                };
+                // An empty chunk signals end of stream
+                let finished = !matches!(result, Ok(ref data) if !data.is_empty());
+                if sender.send(result).is_err() || finished {
+                    return;
+                }
+            }
+        });
+
+        TimedReader {
+            chunks,
+            buffer: VecDeque::new(),
+            chunk_ends: VecDeque::new(),
+            idle_timeout,
+            ended: false,
+            pending_error: None,
+            consumed: 0,
+        }
+    }
+
+    /// Number of bytes that can be read right now without blocking.
+    pub fn buffered(&mut self) -> usize {
+        while let Ok(received) = self.chunks.try_recv() {
+            match received {
+                Ok(chunk) if chunk.is_empty() => self.ended = true,
+                Ok(chunk) => self.push_chunk(chunk),
+                Err(e) => {
+                    // Surface the error on the next read instead
+                    self.pending_error = Some(e);
+                    break;
+                }
+            }
+        }
+        self.buffer.len()
+    }
+
+    fn push_chunk(&mut self, chunk: Vec<u8>) {
+        self.forget_consumed_chunks();
+        self.buffer.extend(chunk);
+        self.chunk_ends.push_back(self.consumed + self.buffer.len() as u64);
+    }
+
+    fn forget_consumed_chunks(&mut self) {
+        while self.chunk_ends.front().is_some_and(|&end| end <= self.consumed) {
+            self.chunk_ends.pop_front();
+        }
+    }
+
+    /// Number of bytes consumed from the stream so far.
+    pub fn position(&self) -> u64 {
+        self.consumed
+    }
+
+    /// Waits until at least `length` bytes are buffered, unless the stream ends or stays idle for
+    /// longer than the timeout first.
+    pub fn fill(&mut self, length: usize) -> io::Result<ReadOutcome> {
+        while self.buffer.len() < length {
+            if let Some(e) = self.pending_error.take() {
+                return Err(e);
+            }
+            if self.ended {
+                return Ok(ReadOutcome::EndOfStream);
+            }
+
+            let received = match self.idle_timeout {
+                Some(timeout) => self.chunks.recv_timeout(timeout),
+                None => self.chunks.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+
+            match received {
+                Ok(Ok(chunk)) if chunk.is_empty() => self.ended = true,
+                Ok(Ok(chunk)) => self.push_chunk(chunk),
+                Ok(Err(e)) => return Err(e),
+                Err(RecvTimeoutError::Timeout) => return Ok(ReadOutcome::IdleTimeout),
+                Err(RecvTimeoutError::Disconnected) => self.ended = true,
+            }
+        }
+        Ok(ReadOutcome::Filled)
+    }
+
+    /// The first `length` buffered bytes, which must have been [`fill`](Self::fill)ed, without
+    /// consuming them.
+    pub fn peek(&mut self, length: usize) -> &[u8] {
+        &self.buffer.make_contiguous()[..length]
+    }
+
+    /// Drops the first `length` buffered bytes.
+    pub fn consume(&mut self, length: usize) {
+        self.buffer.drain(..length);
+        self.consumed += length as u64;
+    }
+
+    /// Fills `buf` completely, unless the stream ends or stays idle for longer than the timeout first.
+    pub fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<ReadOutcome> {
+        match self.fill(buf.len())? {
+            ReadOutcome::Filled => {}
+            outcome => return Ok(outcome),
+        }
+
+        let length = buf.len();
+        for (dst, src) in buf.iter_mut().zip(self.buffer.drain(..length)) {
+            *dst = src;
+        }
+        self.consumed += length as u64;
+        Ok(ReadOutcome::Filled)
+    }
+
+    /// Reads the rest of the next chunk that a single read of the source returned, e.g. a whole
+    /// datagram, unless the stream ends or stays idle for longer than the timeout first.
+    pub fn read_chunk(&mut self, chunk: &mut Vec<u8>) -> io::Result<ReadOutcome> {
+        match self.fill(1)? {
+            ReadOutcome::Filled => {}
+            outcome => return Ok(outcome),
+        }
+
+        self.forget_consumed_chunks();
+        let length = (self.chunk_ends[0] - self.consumed) as usize;
+        chunk.clear();
+        chunk.extend(self.buffer.drain(..length));
+        self.consumed += length as u64;
+        Ok(ReadOutcome::Filled)
+    }
+}
@@ -0,0 +1,280 @@
+//! Encoded stream inspector (`inspect`)
+//!
+//! Reads an encoded stream with the same flags as `--decode`, but only parses it: the report shows
+//! the OTI and the partition derived from it, and for every source block which symbols arrived,
+//! without running the decoder.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use anyhow::{Result, bail};
+use clap::Args;
+use raptorq::{EncodingPacket, ObjectTransmissionInformation, partition};
+
+use crate::input::{ReadOutcome, TimedReader};
+use crate::packet::PacketLayout;
+
+// Duplicate ESIs listed per block in the text report; the JSON report lists all of them
+const MAX_LISTED_DUPLICATES: usize = 10;
+
+// (IL, IS, JL, JS) as returned by the Partition[] function of section 4.4.1.2
+type Partition = (u32, u32, u32, u32);
+
+#[derive(Args)]
+pub struct InspectArgs {
+    #[arg(long, help = "Print the report as a single line of JSON")]
+    json: bool,
+}
+
+/// Packets counted up to and including the first intact one, while the OTI was being read
+pub struct PacketsBeforeOti {
+    pub read: u64,
+    pub corrupted: u64,
+}
+
+#[derive(Default)]
+struct BlockInspection {
+    source_symbols: u32,
+    source_packets: u64,
+    repair_packets: u64,
+    esis: HashSet<u32>,
+    duplicate_packets: u64,
+    duplicate_esis: BTreeSet<u32>,
+}
+
+impl BlockInspection {
+    fn add(&mut self, esi: u32) {
+        if esi < self.source_symbols {
+            self.source_packets += 1;
+        } else {
+            self.repair_packets += 1;
+        }
+        if !self.esis.insert(esi) {
+            self.duplicate_packets += 1;
+            self.duplicate_esis.insert(esi);
+        }
+    }
+
+    fn has_enough_symbols(&self) -> bool {
+        self.esis.len() >= self.source_symbols as usize
+    }
+}
+
+struct Report {
+    config: ObjectTransmissionInformation,
+    packet_size: usize,
+    packets: u64,
+    corrupted_packets: u64,
+    malformed_packets: u64,
+    blocks: Vec<BlockInspection>,
+    // Packets per source block number that the object does not have
+    out_of_range: BTreeMap<u8, u64>,
+    trailing_bytes: u64,
+    end: &'static str,
+}
+
+/// Reads the rest of the stream after its OTI, and prints what it contains
+pub fn inspect(args: &InspectArgs, layout: &PacketLayout, stdin: &mut TimedReader, config: ObjectTransmissionInformation, first_batch: Vec<EncodingPacket>, before_oti: PacketsBeforeOti, max_packets: Option<u64>) -> Result<()> {
+    let packet_size = layout.packet_size(config.symbol_size() as usize);
+    let mut report = Report {
+        config,
+        packet_size,
+        packets: before_oti.read,
+        corrupted_packets: before_oti.corrupted,
+        malformed_packets: before_oti.read - before_oti.corrupted - first_batch.len() as u64,
+        blocks: report_blocks(&config),
+        out_of_range: BTreeMap::new(),
+        trailing_bytes: 0,
+        end: "end_of_stream",
+    };
+    for packet in &first_batch {
+        report.add(packet);
+    }
+
+    let mut packet_buffer = Vec::with_capacity(layout.framing.max_frame_size(packet_size));
+    let mut last_packet_end = stdin.position();
+    loop {
+        if max_packets.is_some_and(|max_packets| report.packets >= max_packets) {
+            report.end = "max_packets";
+            break;
+        }
+        match layout.framing.read_packet(stdin, packet_size, &mut packet_buffer) {
+            Ok(ReadOutcome::Filled) => {}
+            Ok(ReadOutcome::EndOfStream) => break,
+            Ok(ReadOutcome::IdleTimeout) => {
+                report.end = "idle_timeout";
+                break;
+            }
+            Err(e) => bail!("Failed to read packet {} from stdin: {}", report.packets + 1, e),
+        }
+        report.packets += 1;
+        last_packet_end = stdin.position();
+
+        match layout.parse(&packet_buffer, &config) {
+            Ok(Some(packet)) => report.add(&packet),
+            Ok(None) => report.corrupted_packets += 1,
+            Err(e) => {
+                report.malformed_packets += 1;
+                log_info!("  → Malformed packet {}: {:#}", report.packets, e);
+            }
+        }
+    }
+    // Whatever follows the last whole packet (or frame) could not be read as one
+    report.trailing_bytes = stdin.position() + stdin.buffered() as u64 - last_packet_end;
+
+    if args.json {
+        println!("{}", report.json());
+    } else {
+        print!("{}", report.text());
+    }
+    Ok(())
+}
+
+fn report_blocks(config: &ObjectTransmissionInformation) -> Vec<BlockInspection> {
+    let kt = config.transfer_length().div_ceil(config.symbol_size() as u64) as u32;
+    let (kl, ks, zl, _) = partition(kt, config.source_blocks());
+    (0..config.source_blocks() as u32)
+        .map(|sbn| BlockInspection {
+            source_symbols: if sbn < zl { kl } else { ks },
+            ..Default::default()
+        })
+        .collect()
+}
+
+impl Report {
+    fn add(&mut self, packet: &EncodingPacket) {
+        // Only the SBN can be out of range: every ESI that fits in the --esi-bits field (at most
+        // 24 bits) is a source or repair symbol of any block
+        let payload_id = packet.payload_id();
+        match self.blocks.get_mut(payload_id.source_block_number() as usize) {
+            Some(block) => block.add(payload_id.encoding_symbol_id()),
+            None => *self.out_of_range.entry(payload_id.source_block_number()).or_default() += 1,
+        }
+    }
+
+    /// Kt, the partition of the object into source blocks (KL, KS, ZL, ZS), and of each symbol
+    /// into sub-symbols (TL, TS, NL, NS, in units of the symbol alignment), as in section 4.4.1.2
+    fn partition(&self) -> (u32, Partition, Partition) {
+        let config = &self.config;
+        let kt = config.transfer_length().div_ceil(config.symbol_size() as u64) as u32;
+        let alignment_units = config.symbol_size() as u32 / config.symbol_alignment() as u32;
+        (
+            kt,
+            partition(kt, config.source_blocks()),
+            partition(alignment_units, config.sub_blocks()),
+        )
+    }
+
+    fn text(&self) -> String {
+        let config = &self.config;
+        let (kt, (kl, ks, zl, zs), (tl, ts, nl, ns)) = self.partition();
+        let mut text = format!(
+            "transfer length (F):    {} bytes\n\
+             symbol size (T):        {} bytes\n\
+             source blocks (Z):      {}\n\
+             sub-blocks (N):         {}\n\
+             symbol alignment (Al):  {}\n\
+             source symbols (Kt):    {}\n\
+             source blocks:          ZL = {} of KL = {} symbols, ZS = {} of KS = {} symbols\n\
+             sub-blocks:             NL = {} of TL = {}, NS = {} of TS = {} (units of Al)\n\
+             packet size:            {} bytes\n\
+             packets:                {}\n\
+             corrupted packets:      {}\n\
+             malformed packets:      {}\n\
+             trailing bytes:         {}\n\
+             end:                    {}\n",
+            config.transfer_length(),
+            config.symbol_size(),
+            config.source_blocks(),
+            config.sub_blocks(),
+            config.symbol_alignment(),
+            kt,
+            zl, kl, zs, ks,
+            nl, tl, ns, ts,
+            self.packet_size,
+            self.packets,
+            self.corrupted_packets,
+            self.malformed_packets,
+            self.trailing_bytes,
+            self.end,
+        );
+
+        text += "\n  SBN        K   source   repair   unique  duplicates  enough\n";
+        for (sbn, block) in self.blocks.iter().enumerate() {
+            text += &format!(
+                "{:>5} {:>8} {:>8} {:>8} {:>8} {:>11}  {}\n",
+                sbn,
+                block.source_symbols,
+                block.source_packets,
+                block.repair_packets,
+                block.esis.len(),
+                block.duplicate_packets,
+                if block.has_enough_symbols() { "yes" } else { "NO" },
+            );
+        }
+
+        for (sbn, block) in self.blocks.iter().enumerate() {
+            if block.duplicate_esis.is_empty() {
+                continue;
+            }
+            let mut listed: Vec<String> = block.duplicate_esis.iter()
+                .take(MAX_LISTED_DUPLICATES)
+                .map(u32::to_string)
+                .collect();
+            if block.duplicate_esis.len() > MAX_LISTED_DUPLICATES {
+                listed.push(format!("and {} more", block.duplicate_esis.len() - MAX_LISTED_DUPLICATES));
+            }
+            text += &format!("duplicate ESIs in block {}: {}\n", sbn, listed.join(", "));
+        }
+        for (sbn, packets) in &self.out_of_range {
+            text += &format!("out-of-range block {}: {} packets\n", sbn, packets);
+        }
+        text
+    }
+
+    fn json(&self) -> String {
+        let config = &self.config;
+        let (kt, (kl, ks, zl, zs), (tl, ts, nl, ns)) = self.partition();
+        let blocks: Vec<String> = self.blocks.iter()
+            .enumerate()
+            .map(|(sbn, block)| {
+                let duplicate_esis: Vec<String> = block.duplicate_esis.iter().map(u32::to_string).collect();
+                format!(
+                    "{{\"sbn\":{},\"source_symbols\":{},\"source_packets\":{},\"repair_packets\":{},\
+                     \"unique_symbols\":{},\"duplicate_packets\":{},\"duplicate_esis\":[{}],\"enough_symbols\":{}}}",
+                    sbn,
+                    block.source_symbols,
+                    block.source_packets,
+                    block.repair_packets,
+                    block.esis.len(),
+                    block.duplicate_packets,
+                    duplicate_esis.join(","),
+                    block.has_enough_symbols(),
+                )
+            })
+            .collect();
+        let out_of_range: Vec<String> = self.out_of_range.iter()
+            .map(|(sbn, packets)| format!("{{\"sbn\":{},\"packets\":{}}}", sbn, packets))
+            .collect();
+
+        format!(
+            "{{\"transfer_length\":{},\"symbol_size\":{},\"source_blocks\":{},\"sub_blocks\":{},\"symbol_alignment\":{},\
+             \"kt\":{},\"kl\":{},\"ks\":{},\"zl\":{},\"zs\":{},\"tl\":{},\"ts\":{},\"nl\":{},\"ns\":{},\
+             \"packet_size\":{},\"packets\":{},\"corrupted_packets\":{},\"malformed_packets\":{},\
+             \"trailing_bytes\":{},\"end\":\"{}\",\"blocks\":[{}],\"out_of_range_blocks\":[{}]}}",
+            config.transfer_length(),
+            config.symbol_size(),
+            config.source_blocks(),
+            config.sub_blocks(),
+            config.symbol_alignment(),
+            kt, kl, ks, zl, zs,
+            tl, ts, nl, ns,
+            self.packet_size,
+            self.packets,
+            self.corrupted_packets,
+            self.malformed_packets,
+            self.trailing_bytes,
+            self.end,
+            blocks.join(","),
+            out_of_range.join(","),
+        )
+    }
+}
@@ -1,16 +1,53 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use std::borrow::Cow;
+use std::fs::File;
 use std::io::{self, Read, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::fs;
+use std::time::{Duration, Instant};
 use anyhow::{Result, Context, bail};
-use raptorq::{Encoder, Decoder, EncodingPacket, ObjectTransmissionInformation};
+use memmap2::Mmap;
+use rayon::prelude::*;
+use raptorq::{
+    Encoder, Decoder, EncodingPacket, ObjectTransmissionInformation, OtiField, OtiProfile, PayloadIdFormat,
+    RepairPlanner, SourceBlockEncoder, SourceBlockEncodingPlan, StreamingEncoder, calculate_block_offsets,
+};
 
 // Conditional logging macro - only logs when "verbose-logging" feature is enabled
 macro_rules! log_info {
     ($($arg:tt)*) => {
-        #[cfg(feature = "verbose-logging")]
-        eprintln!($($arg)*);
+        if cfg!(feature = "verbose-logging") {
+            eprintln!($($arg)*);
+        }
     };
 }
 
+mod checksum;
+mod framing;
+mod input;
+mod inspect;
+mod output;
+mod packet;
+mod serve;
+mod simulate;
+mod udp;
+
+use checksum::Checksum;
+use framing::Framing;
+use input::{ReadOutcome, TimedReader};
+use inspect::{InspectArgs, PacketsBeforeOti};
+use output::{BlockFormat, BlockWriter, open_output};
+use packet::{PacketLayout, PacketWriter};
+use simulate::SimulateArgs;
+use udp::{DatagramReceiver, DatagramSender, RecvArgs, SendArgs};
+
+// Upper bound on the number of packets handed to the decoder at once
+const MAX_DECODE_BATCH: usize = 4096;
+
+// Time between checkpoints of the decoder state while packets keep arriving
+const STATE_SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
 /// RFC6330 compliant RaptorQ encoder/decoder
 /// 
 /// This implementation properly handles:
@@ -31,36 +68,210 @@ macro_rules! log_info {
 #[command(about = "RaptorQ forward error correction CLI tool")]
 #[command(version)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[arg(long, conflicts_with = "decode", help = "Encode data from stdin")]
     encode: bool,
     
     #[arg(long, conflicts_with = "encode", help = "Decode data from stdin (reads all parameters from OTI header)")]
     decode: bool,
+
+    #[arg(long, conflicts_with_all = ["encode", "decode"], help = "Serve many encode/decode jobs over a framed stdin/stdout protocol")]
+    serve: bool,
     
     // Encoding-only parameters (ignored during decoding - OTI is used instead)
-    #[arg(long, default_value = "1400", help = "Size of each symbol in bytes (MTU) - ENCODING ONLY")]
+    #[arg(long, global = true, default_value = "1400", help = "Size of each symbol in bytes (MTU) - ENCODING ONLY")]
     symbol_size: u16,
     
-    #[arg(long, default_value = "15", help = "Number of repair symbols per source block - ENCODING ONLY")]
+    #[arg(long, global = true, default_value = "15", help = "Number of repair symbols per source block - ENCODING ONLY")]
     repair_symbols: u32,
+
+    #[arg(long, global = true, value_name = "PERCENT", conflicts_with = "repair_symbols", help = "Number of repair symbols per source block as a percentage of its source symbols, rounded up - ENCODING ONLY")]
+    repair_overhead: Option<f64>,
+
+    #[arg(long, global = true, value_name = "FRACTION", conflicts_with_all = ["repair_symbols", "repair_overhead"], help = "Choose the number of repair symbols per source block for a channel losing this fraction of packets - ENCODING ONLY")]
+    target_loss: Option<f64>,
+
+    #[arg(long, global = true, value_name = "PROBABILITY", default_value = "1e-6", requires = "target_loss", help = "Highest acceptable probability that the object fails to decode with --target-loss - ENCODING ONLY")]
+    target_failure: f64,
+
+    #[arg(long, global = true, value_name = "PACKETS", default_value = "1", value_parser = clap::value_parser!(u32).range(1..), requires = "target_loss", help = "Packets are lost in bursts of this many packets with --target-loss - ENCODING ONLY")]
+    target_burst_length: u32,
     
-    #[arg(long, default_value = "1", help = "Number of source blocks - ENCODING ONLY")]
+    #[arg(long, global = true, default_value = "1", help = "Number of source blocks - ENCODING ONLY")]
     source_blocks: u8,
     
-    #[arg(long, default_value = "1", help = "Number of sub-blocks per source block - ENCODING ONLY")]
+    #[arg(long, global = true, default_value = "1", help = "Number of sub-blocks per source block - ENCODING ONLY")]
     sub_blocks: u16,
     
-    #[arg(long, default_value = "8", help = "Symbol alignment in bytes (must be > 0) - ENCODING ONLY")]
+    #[arg(long, global = true, default_value = "8", help = "Symbol alignment in bytes (must be > 0) - ENCODING ONLY")]
     symbol_alignment: u8,
+
+    #[arg(long, global = true, help = "After the source packets, emit repair packets round-robin across all source blocks until the output is closed - ENCODING ONLY")]
+    endless: bool,
+
+    #[arg(long, global = true, help = "Length of the input in bytes; encodes block by block while reading stdin (also the fixed transfer length when --oti-transfer-length-bits is 0)")]
+    transfer_length: Option<u64>,
+
+    #[arg(long, global = true, value_name = "PATH", help = "Read input from this file (memory-mapped when encoding) instead of stdin")]
+    input: Option<PathBuf>,
+
+    #[arg(long, global = true, value_enum, help = "Append this checksum to every packet when encoding, and drop packets that fail it when decoding")]
+    checksum: Option<Checksum>,
+
+    #[arg(long, value_enum, default_value = "fixed", conflicts_with = "serve", help = "How packets are delimited on stdout when encoding and on stdin when decoding")]
+    framing: Framing,
+
+    #[arg(long, global = true, default_value = "8", help = "Width of the Source Block Number in each packet's PayloadId, 0-8 bits (0 omits it, allowing a single source block)")]
+    sbn_bits: u8,
+
+    #[arg(long, global = true, default_value = "24", help = "Width of the Encoding Symbol ID in each packet's PayloadId, 2-24 bits")]
+    esi_bits: u8,
+
+    #[arg(long, global = true, default_value = "40", help = "Bits of the transfer length in the OTI header, 0-40 (0 omits it; both sides then use --transfer-length)")]
+    oti_transfer_length_bits: u8,
+
+    #[arg(long, global = true, help = "Omit the 8-bit FEC Encoding ID (reserved) field from the OTI header")]
+    oti_omit_fec_encoding_id: bool,
+
+    #[arg(long, conflicts_with = "serve", help = "Write the OTI at the start of every packet instead of once as a header, so decoding can start from any packet")]
+    oti_per_packet: bool,
+
+    #[arg(long, global = true, default_value = "16", help = "Bits of the symbol size in the OTI header, 0-16 (0 omits it; both sides then use --symbol-size)")]
+    oti_symbol_size_bits: u8,
+
+    #[arg(long, global = true, default_value = "8", help = "Bits of the number of source blocks in the OTI header, 0-8 (0 omits it; both sides then use --source-blocks)")]
+    oti_source_blocks_bits: u8,
+
+    #[arg(long, global = true, default_value = "16", help = "Bits of the number of sub-blocks in the OTI header, 0-16 (0 omits it; both sides then use --sub-blocks)")]
+    oti_sub_blocks_bits: u8,
+
+    #[arg(long, global = true, default_value = "8", help = "Bits of the symbol alignment in the OTI header, 0-8 (0 omits it; both sides then use --symbol-alignment)")]
+    oti_symbol_alignment_bits: u8,
+
+    #[arg(long, global = true, help = "Number of threads used to encode and decode source blocks in parallel (default: one per CPU)")]
+    threads: Option<usize>,
+
+    #[arg(long, global = true, value_name = "PATH", help = "Write output to this file instead of stdout; decoding writes each block at its offset")]
+    output: Option<PathBuf>,
+
+    // Decoding-only parameters
+    #[arg(long, global = true, conflicts_with = "output", help = "Prefix each decoded block with its byte offset in the object and its exact length - DECODING ONLY")]
+    block_offsets: bool,
+
+    #[arg(long, global = true, conflicts_with_all = ["output", "block_offsets"], help = "Write the decoded object itself, in order, instead of SBN-prefixed blocks - DECODING ONLY")]
+    combined: bool,
+
+    #[arg(long, global = true, conflicts_with_all = ["combined", "block_offsets"], help = "Write each source symbol as soon as it arrives, and the rest of a block once it is decoded, each prefixed with its offset and length - DECODING ONLY")]
+    progressive: bool,
+
+    #[arg(long, global = true, value_name = "PATH", conflicts_with = "progressive", help = "Resume decoding from the state saved in this file, and save the state to it when giving up - DECODING ONLY")]
+    state_file: Option<PathBuf>,
+
+    #[arg(long, global = true, help = "Give up if no input arrives for this many seconds - DECODING ONLY")]
+    idle_timeout: Option<f64>,
+
+    #[arg(long, global = true, help = "Give up after reading this many packets without completing - DECODING ONLY")]
+    max_packets: Option<u64>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Encode stdin (or --input) and send one packet per UDP datagram
+    Send(SendArgs),
+    /// Receive packets from UDP datagrams and decode them, exiting once the object is complete
+    Recv(RecvArgs),
+    /// Measure decoding success and overhead for the encoding parameters over a simulated lossy channel
+    Simulate(SimulateArgs),
+    /// Parse an encoded stream and report its OTI and the symbols received for every source block, without decoding
+    Inspect(InspectArgs),
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
     
-    // Validate that either encode or decode is specified
-    if !args.encode && !args.decode {
-        bail!("Either --encode or --decode must be specified");
+    // Validate that either encode, decode, serve or a subcommand is specified
+    let mode_selected = args.encode || args.decode || args.serve;
+    if args.command.is_some() && mode_selected {
+        bail!("--encode, --decode and --serve cannot be combined with a subcommand");
+    }
+    if args.command.is_none() && !mode_selected {
+        bail!("Either --encode, --decode, --serve or a subcommand must be specified");
+    }
+    // Checked here rather than by clap, which cannot relate these global flags to the mode flags
+    if args.endless && (args.decode || args.serve || matches!(args.command, Some(Command::Recv(_) | Command::Simulate(_) | Command::Inspect(_)))) {
+        bail!("--endless can only be used when encoding");
+    }
+    if args.state_file.is_some() && !(args.decode || matches!(args.command, Some(Command::Recv(_)))) {
+        bail!("--state-file can only be used when decoding");
+    }
+    if args.serve && (args.input.is_some() || args.output.is_some()) {
+        bail!("--serve cannot be combined with --input or --output");
+    }
+    match args.command {
+        Some(Command::Send(_)) if args.output.is_some() => bail!("send writes to UDP and cannot be combined with --output"),
+        Some(Command::Recv(_)) if args.input.is_some() => bail!("recv reads from UDP and cannot be combined with --input"),
+        Some(Command::Inspect(_)) if args.output.is_some() => bail!("inspect prints its report to stdout and cannot be combined with --output"),
+        _ => {}
+    }
+    if let Some(percent) = args.repair_overhead {
+        if !(percent.is_finite() && percent >= 0.0) {
+            bail!("Repair overhead must be a non-negative percentage");
+        }
     }
+    if let Some(loss) = args.target_loss {
+        RepairPlanner::try_new(loss, args.target_failure).context("Invalid --target-loss or --target-failure")?;
+    }
+
+    if let Some(seconds) = args.idle_timeout {
+        if !(seconds.is_finite() && seconds > 0.0) {
+            bail!("Idle timeout must be a positive number of seconds");
+        }
+    }
+
+    if let Some(threads) = args.threads {
+        if threads == 0 {
+            bail!("Thread count must be greater than 0");
+        }
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .context("Failed to start thread pool")?;
+    }
+
+    // Jobs in serve mode carry their own encoding parameters
+    if args.serve {
+        return serve::serve();
+    }
+
+    if let Some(Command::Simulate(simulate)) = &args.command {
+        let Some(transfer_length) = args.transfer_length else {
+            bail!("simulate needs --transfer-length for the size of the simulated object");
+        };
+        let config = ObjectTransmissionInformation::try_new(
+            transfer_length,
+            args.symbol_size,
+            args.source_blocks,
+            args.sub_blocks,
+            args.symbol_alignment,
+        ).context("Invalid encoding parameters")?;
+        return simulate::simulate(simulate, config, |block_encoder| repair_symbols_per_block(&args, block_encoder));
+    }
+
+    // A receiver may miss any datagram, so over UDP every packet carries the OTI
+    let layout = PacketLayout {
+        oti_profile: oti_profile(&args)?,
+        oti_per_packet: args.oti_per_packet || matches!(args.command, Some(Command::Send(_) | Command::Recv(_))),
+        payload_id_format: PayloadIdFormat::try_new(args.sbn_bits, args.esi_bits)
+            .context("Invalid PayloadId format")?,
+        checksum: args.checksum,
+        framing: match args.command {
+            Some(Command::Send(_)) => Framing::Fixed,
+            Some(Command::Recv(_)) => DatagramReceiver::FRAMING,
+            _ => args.framing,
+        },
+    };
     
     // Validate symbol alignment
     if args.symbol_alignment == 0 {
@@ -72,14 +283,98 @@ fn main() -> Result<()> {
         bail!("Symbol size must be divisible by symbol alignment");
     }
     
-    if args.encode {
-        encode_data(&args)
+    if let Some(Command::Inspect(inspect)) = &args.command {
+        let mut stdin = open_decode_input(&args)?;
+        let mut packets_processed: u64 = 0;
+        let mut corrupted_packets: u64 = 0;
+        let (config, first_batch) = read_oti(&args, &layout, &mut stdin, &mut packets_processed, &mut corrupted_packets)?;
+        let before_oti = PacketsBeforeOti { read: packets_processed, corrupted: corrupted_packets };
+        return inspect::inspect(inspect, &layout, &mut stdin, config, first_batch, before_oti, args.max_packets);
+    }
+
+    if args.encode || matches!(args.command, Some(Command::Send(_))) {
+        if args.source_blocks as u16 > layout.payload_id_format.max_source_block_number() as u16 + 1 {
+            bail!("{} source blocks do not fit in --sbn-bits {}", args.source_blocks, args.sbn_bits);
+        }
+
+        match encode_data(&args, &layout) {
+            // In endless mode the receiver closing the pipe is the normal way for encoding to end
+            Err(e) if args.endless && is_broken_pipe(&e) => {
+                log_info!("Output closed - stopping endless encode");
+                Ok(())
+            }
+            result => result,
+        }
     } else {
-        decode_data(&args)
+        decode_data(&args, &layout)
+    }
+}
+
+fn is_broken_pipe(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause.downcast_ref::<io::Error>().is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe)
+    })
+}
+
+/// Builds the OTI header layout from the --oti-*-bits flags. Omitted fields take the value of the
+/// matching encoding parameter, which the decoder must then be given as well.
+fn oti_profile(args: &Args) -> Result<OtiProfile> {
+    let field = |bits: u8, fixed: u64| if bits == 0 { OtiField::Fixed(fixed) } else { OtiField::Bits(bits) };
+    let transfer_length = match (args.oti_transfer_length_bits, args.transfer_length) {
+        (0, None) => bail!("--oti-transfer-length-bits 0 requires --transfer-length"),
+        (bits, transfer_length) => field(bits, transfer_length.unwrap_or(0)),
+    };
+    OtiProfile::try_new(
+        transfer_length,
+        !args.oti_omit_fec_encoding_id,
+        field(args.oti_symbol_size_bits, args.symbol_size as u64),
+        field(args.oti_source_blocks_bits, args.source_blocks as u64),
+        field(args.oti_sub_blocks_bits, args.sub_blocks as u64),
+        field(args.oti_symbol_alignment_bits, args.symbol_alignment as u64),
+    ).context("Invalid OTI profile")
+}
+
+/// Number of repair packets written right after a block's source packets; in endless mode
+/// they are all written by the carousel instead
+fn repair_symbols_per_block(args: &Args, block_encoder: &SourceBlockEncoder) -> Result<u32> {
+    if args.endless {
+        return Ok(0);
+    }
+    let source_symbols = block_encoder.source_symbol_count();
+    if let Some(loss) = args.target_loss {
+        let mut planner = RepairPlanner::try_new(loss, args.target_failure)?;
+        planner.set_burst_length(args.target_burst_length);
+        let repair_symbols = planner.repair_symbols(source_symbols, args.source_blocks)
+            .context("Failed to plan repair symbols")?;
+        log_info!("  → {} repair symbols for {} source symbols (predicted block failure probability {:.2e})",
+            repair_symbols, source_symbols, planner.block_failure_probability(source_symbols, repair_symbols));
+        return Ok(repair_symbols);
+    }
+    Ok(match args.repair_overhead {
+        Some(percent) => (source_symbols as f64 * percent / 100.0).ceil() as u32,
+        None => args.repair_symbols,
+    })
+}
+
+/// Opens the destination for encoded output: UDP datagrams for `send`, otherwise the `--output`
+/// file or stdout
+fn open_encode_output(args: &Args) -> Result<Box<dyn Write>> {
+    match &args.command {
+        Some(Command::Send(send)) => Ok(Box::new(DatagramSender::open(send)?)),
+        _ => open_output(args.output.as_deref()),
     }
 }
 
-fn encode_data(args: &Args) -> Result<()> {
+fn encode_data(args: &Args, layout: &PacketLayout) -> Result<()> {
+    if let Some(path) = &args.input {
+        return encode_file(args, layout, path);
+    }
+
+    // With a known transfer length the object is encoded block by block as it arrives
+    if let Some(transfer_length) = args.transfer_length {
+        return encode_stream(args, layout, transfer_length);
+    }
+
     // Read input data from stdin
     let mut input_data = Vec::new();
     io::stdin().read_to_end(&mut input_data)
@@ -92,63 +387,35 @@ fn encode_data(args: &Args) -> Result<()> {
     // Create encoder - use defaults with user overrides
     // Transfer length is automatically determined from input data size
     let transfer_length = input_data.len() as u64;
-    let config = ObjectTransmissionInformation::new(
+    let config = ObjectTransmissionInformation::try_new(
         transfer_length,
         args.symbol_size,
         args.source_blocks,
         args.sub_blocks,
         args.symbol_alignment,
-    );
+    ).context("Invalid encoding parameters")?;
     let encoder = Encoder::new(&input_data, config);
 
-    // Output OTI header immediately (12 bytes as per RFC6330)
+    // Output OTI header immediately (12 bytes as per RFC6330, unless shortened by --oti-*-bits)
     // This allows the decoder to automatically determine all encoding parameters
-    let oti = config.serialize();
-    io::stdout().write_all(&oti)
-        .context("Failed to write OTI header to stdout")?;
+    let mut output = open_encode_output(args)?;
+    let oti = layout.oti_header(&config)?;
+    output.write_all(&oti)
+        .context("Failed to write OTI header")?;
+    let mut packet_writer = PacketWriter::new(layout, &config)?;
     
     log_info!("Starting streaming encode - outputting packets as they're generated...");
     
     // Stream encoded packets as they're generated (no buffering)
     let mut total_packets = 0;
-    let mut stdout = io::stdout();
     
     for (block_idx, block_encoder) in encoder.get_block_encoders().iter().enumerate() {
         log_info!("Processing source block {} of {}", block_idx + 1, encoder.get_block_encoders().len());
-        
-        // Stream source packets immediately
-        let source_packets = block_encoder.source_packets();
-        let source_packet_count = source_packets.len(); // Store length before move
-        for packet in source_packets {
-            let serialized = packet.serialize();
-            stdout.write_all(&serialized)
-                .context("Failed to write source packet to stdout")?;
-            total_packets += 1;
-        }
-        
-        // Stream repair packets in batches to avoid memory buildup
-        let repair_batch_size = 50; // Process repair packets in smaller batches
-        let mut repair_start = 0;
-        
-        while repair_start < args.repair_symbols {
-            let batch_size = std::cmp::min(repair_batch_size, args.repair_symbols - repair_start);
-            let repair_packets = block_encoder.repair_packets(repair_start, batch_size);
-            
-            for packet in repair_packets {
-                let serialized = packet.serialize();
-                stdout.write_all(&serialized)
-                    .context("Failed to write repair packet to stdout")?;
-                total_packets += 1;
-            }
-            
-            repair_start += batch_size;
-            log_info!("  → Generated {} repair packets so far", repair_start);
-        }
-        
-        // Ensure packets are written immediately
-        stdout.flush().context("Failed to flush stdout")?;
-        log_info!("✓ Completed source block {} ({} packets)", block_idx + 1, 
-            source_packet_count + args.repair_symbols as usize);
+        total_packets += write_block_packets(&mut output, block_encoder, repair_symbols_per_block(args, block_encoder)?, &mut packet_writer)?;
+    }
+
+    if args.endless {
+        return write_repair_carousel(&mut output, encoder.get_block_encoders(), layout, &mut packet_writer);
     }
     
     log_info!("✓ Successfully encoded {} bytes into {} packets (streamed output)", 
@@ -156,16 +423,238 @@ fn encode_data(args: &Args) -> Result<()> {
     Ok(())
 }
 
-fn decode_data(args: &Args) -> Result<()> {
-    let mut stdin = io::stdin();
+/// Encodes an object of a declared length from stdin, holding at most one source block in memory
+fn encode_stream(args: &Args, layout: &PacketLayout, transfer_length: u64) -> Result<()> {
+    let config = ObjectTransmissionInformation::try_new(
+        transfer_length,
+        args.symbol_size,
+        args.source_blocks,
+        args.sub_blocks,
+        args.symbol_alignment,
+    ).context("Invalid encoding parameters")?;
+    let mut encoder = StreamingEncoder::new(config);
+    let total_blocks = encoder.block_offsets().len();
+
+    // The OTI only depends on the declared transfer length, so it goes out before any input is read
+    let mut output = open_encode_output(args)?;
+    output.write_all(&layout.oti_header(&config)?)
+        .context("Failed to write OTI header")?;
+    let mut packet_writer = PacketWriter::new(layout, &config)?;
+    output.flush().context("Failed to flush output")?;
+
+    log_info!("Streaming encode of {} bytes in {} source blocks...", transfer_length, total_blocks);
+
+    let mut stdin = io::stdin().lock();
+    let mut block_buffer = Vec::new();
+    let mut blocks_encoded = 0;
+    let mut total_packets = 0;
+    let mut carousel_blocks = Vec::new();
+
+    while !encoder.is_complete() {
+        // Read exactly the rest of the current source block, then encode and emit it
+        let needed = encoder.bytes_needed();
+        block_buffer.resize(needed, 0);
+        let mut filled = 0;
+        while filled < needed {
+            match stdin.read(&mut block_buffer[filled..]) {
+                Ok(0) => bail!(
+                    "Input ended after {} bytes, but --transfer-length is {} bytes",
+                    transfer_length - encoder.remaining_bytes() + filled as u64,
+                    transfer_length
+                ),
+                Ok(n) => filled += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e).context("Failed to read from stdin"),
+            }
+        }
+
+        for block_encoder in encoder.push(&block_buffer) {
+            blocks_encoded += 1;
+            log_info!("Processing source block {} of {}", blocks_encoded, total_blocks);
+            total_packets += write_block_packets(&mut output, &block_encoder, repair_symbols_per_block(args, &block_encoder)?, &mut packet_writer)?;
+            if args.endless {
+                // The block buffer is reused for the next block, so the carousel keeps a copy
+                carousel_blocks.push(block_encoder.into_owned());
+            }
+        }
+    }
+
+    // Anything left on stdin means the declared length was wrong, and the receiver would silently
+    // lose the tail of the object
+    let mut extra = [0u8; 1];
+    loop {
+        match stdin.read(&mut extra) {
+            Ok(0) => break,
+            Ok(_) => bail!("Input is longer than --transfer-length of {} bytes", transfer_length),
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e).context("Failed to read from stdin"),
+        }
+    }
+
+    if args.endless {
+        return write_repair_carousel(&mut output, &carousel_blocks, layout, &mut packet_writer);
+    }
+
+    log_info!("✓ Successfully encoded {} bytes into {} packets (streamed input and output)",
+        transfer_length, total_packets);
+    Ok(())
+}
+
+/// Encodes a file through a read-only memory map, handing each source block to the encoder in place
+fn encode_file(args: &Args, layout: &PacketLayout, path: &Path) -> Result<()> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open input file {}", path.display()))?;
+    let length = file.metadata()
+        .with_context(|| format!("Failed to read metadata of {}", path.display()))?
+        .len();
+    if length == 0 {
+        bail!("Input file {} is empty", path.display());
+    }
+    if args.transfer_length.is_some_and(|transfer_length| transfer_length != length) {
+        bail!("Input file {} is {} bytes, but --transfer-length is {} bytes", path.display(), length, args.transfer_length.unwrap());
+    }
+
+    // Safety: the input file must not be modified while it is being encoded
+    let data = unsafe { Mmap::map(&file) }
+        .with_context(|| format!("Failed to memory-map input file {}", path.display()))?;
+
+    let config = ObjectTransmissionInformation::try_new(
+        data.len() as u64,
+        args.symbol_size,
+        args.source_blocks,
+        args.sub_blocks,
+        args.symbol_alignment,
+    ).context("Invalid encoding parameters")?;
+
+    let mut output = open_encode_output(args)?;
+    output.write_all(&layout.oti_header(&config)?)
+        .context("Failed to write OTI header")?;
+    let mut packet_writer = PacketWriter::new(layout, &config)?;
+
+    let block_offsets = calculate_block_offsets(&data, &config);
+
+    // Blocks come in at most two sizes, each of which gets one shared encoding plan
+    let mut plans: Vec<(usize, SourceBlockEncodingPlan)> = Vec::new();
+    for &(start, end) in &block_offsets {
+        let symbol_count = (end - start) / config.symbol_size() as usize;
+        if !plans.iter().any(|(count, _)| *count == symbol_count) {
+            plans.push((symbol_count, SourceBlockEncodingPlan::generate(symbol_count as u16)));
+        }
+    }
+
+    let encode_block = |(block_idx, &(start, end)): (usize, &(usize, usize))| {
+        // Only the final blocks can extend past the end of the file, and the block encoder zero
+        // pads them without copying the file
+        let block = &data[start.min(data.len())..end.min(data.len())];
+
+        let symbol_count = (end - start) / config.symbol_size() as usize;
+        let (_, plan) = plans.iter().find(|(count, _)| *count == symbol_count).unwrap();
+        SourceBlockEncoder::with_encoding_plan(block_idx as u8, &config, block, plan)
+    };
+
+    // Encode one block per thread at a time, so memory use stays bounded by the thread count
+    // (except in endless mode, where every block keeps producing repair packets)
+    let mut total_packets = 0;
+    let mut carousel_blocks = Vec::new();
+    let wave_size = rayon::current_num_threads();
+    for (wave_idx, wave) in block_offsets.chunks(wave_size).enumerate() {
+        let block_encoders: Vec<SourceBlockEncoder> = wave.par_iter()
+            .enumerate()
+            .map(|(i, offsets)| encode_block((wave_idx * wave_size + i, offsets)))
+            .collect();
+
+        for block_encoder in &block_encoders {
+            total_packets += write_block_packets(&mut output, block_encoder, repair_symbols_per_block(args, block_encoder)?, &mut packet_writer)?;
+        }
+        log_info!("Processed {} of {} source blocks", wave_idx * wave_size + wave.len(), block_offsets.len());
+
+        if args.endless {
+            carousel_blocks.extend(block_encoders);
+        }
+    }
+
+    if args.endless {
+        return write_repair_carousel(&mut output, &carousel_blocks, layout, &mut packet_writer);
+    }
+
+    log_info!("✓ Successfully encoded {} bytes into {} packets (memory-mapped input)",
+        data.len(), total_packets);
+    Ok(())
+}
+
+/// Writes every source packet of a block followed by its repair packets, returning the packet count
+fn write_block_packets(output: &mut impl Write, block_encoder: &SourceBlockEncoder, repair_symbols: u32, packet_writer: &mut PacketWriter) -> Result<usize> {
+    // Every packet is serialized into the writer's buffer, so memory use does not grow with the
+    // number of repair symbols
+    let mut total_packets = 0;
+
+    // Stream source packets immediately
+    let source_symbols = block_encoder.source_symbol_count();
+    for source_symbol_id in 0..source_symbols {
+        let length = block_encoder.write_source_packet(source_symbol_id, packet_writer.packet_buffer());
+        output.write_all(packet_writer.finish(length)?)
+            .context("Failed to write source packet")?;
+        total_packets += 1;
+    }
     
-    // First, read the OTI header (12 bytes) from stdin
-    let mut oti_buffer = [0u8; 12];
-    stdin.read_exact(&mut oti_buffer)
-        .context("Failed to read OTI header from stdin")?;
+    // Repair packets are computed one at a time, as they are written
+    let mut repair_packets = block_encoder.repair_packet_iter(0);
+    for _ in 0..repair_symbols {
+        let Some(length) = repair_packets.write_next(packet_writer.packet_buffer()) else {
+            bail!("Ran out of encoding symbol IDs after {} repair packets", total_packets - source_symbols as usize);
+        };
+        output.write_all(packet_writer.finish(length)?)
+            .context("Failed to write repair packet")?;
+        total_packets += 1;
+    }
+    
+    // Ensure packets are written immediately
+    output.flush().context("Failed to flush output")?;
+    log_info!("✓ Completed source block ({} packets)", total_packets);
+    Ok(total_packets)
+}
 
-    // Parse ObjectTransmissionInformation (OTI) from stream
-    let config = ObjectTransmissionInformation::deserialize(&oti_buffer);
+/// Emits repair packets round-robin across all blocks with increasing ESIs, until the output is
+/// closed or every block has run out of encoding symbol IDs that fit in --esi-bits
+fn write_repair_carousel(output: &mut impl Write, block_encoders: &[SourceBlockEncoder], layout: &PacketLayout, packet_writer: &mut PacketWriter) -> Result<()> {
+    log_info!("Starting repair carousel over {} source blocks...", block_encoders.len());
+
+    let max_encoding_symbol_id = layout.payload_id_format.max_encoding_symbol_id();
+    let mut repair_packets: Vec<_> = block_encoders.iter()
+        .map(|block_encoder| {
+            let repair_symbols = (max_encoding_symbol_id + 1).saturating_sub(block_encoder.source_symbol_count());
+            (block_encoder.repair_packet_iter(0), repair_symbols)
+        })
+        .collect();
+
+    loop {
+        let mut packets_written = 0;
+        for (block_repair_packets, repair_symbols_left) in repair_packets.iter_mut() {
+            if *repair_symbols_left == 0 {
+                continue;
+            }
+            if let Some(length) = block_repair_packets.write_next(packet_writer.packet_buffer()) {
+                output.write_all(packet_writer.finish(length)?)
+                    .context("Failed to write repair packet")?;
+                *repair_symbols_left -= 1;
+                packets_written += 1;
+            }
+        }
+        if packets_written == 0 {
+            log_info!("✓ Every source block has run out of encoding symbol IDs");
+            return Ok(());
+        }
+
+        // Keep late-joining receivers fed with complete rounds
+        output.flush().context("Failed to flush output")?;
+    }
+}
+
+fn decode_data(args: &Args, layout: &PacketLayout) -> Result<()> {
+    let mut stdin = open_decode_input(args)?;
+    let mut packets_processed: u64 = 0;
+    let mut corrupted_packets: u64 = 0;
+    let (config, mut first_batch) = read_oti(args, layout, &mut stdin, &mut packets_processed, &mut corrupted_packets)?;
     
     log_info!("Using OTI from stream:");
     log_info!("  transfer_length: {} bytes", config.transfer_length());
@@ -175,76 +664,315 @@ fn decode_data(args: &Args) -> Result<()> {
     log_info!("  symbol_alignment: {}", config.symbol_alignment());
     
     // Calculate packet size
-    // Each packet = PayloadId (4 bytes) + symbol data (symbol_size bytes)
-    let packet_size = 4 + config.symbol_size() as usize;
-    log_info!("Each packet is {} bytes (4 byte PayloadId + {} byte symbol)", 
-        packet_size, config.symbol_size());
-    log_info!("Output format: blocks (always - SBN-prefixed for concurrency)");
-    
-    // Create a single decoder that handles all source blocks internally
-    let mut decoder = Decoder::new(config);
+    // Each packet = optional OTI + PayloadId (--sbn-bits + --esi-bits, in whole bytes) + symbol data (symbol_size bytes) + optional checksum
+    let packet_size = layout.packet_size(config.symbol_size() as usize);
+    log_info!("Each packet is {} bytes ({} byte OTI + {} byte PayloadId + {} byte symbol + checksum)", 
+        packet_size, layout.embedded_oti_size(), layout.payload_id_format.serialized_size(), config.symbol_size());
+    // A whole packet is only certain to be buffered once this many bytes are
+    let frame_size = layout.framing.max_frame_size(packet_size);
+    
+    // Create a single decoder that handles all source blocks internally, or pick up where an
+    // earlier run left off
+    let mut decoder = match &args.state_file {
+        Some(path) if path.exists() => load_state(path, &config)?,
+        _ => Decoder::new(config),
+    };
+    first_batch.retain(|packet| decoder.check_packet(packet).is_ok());
+    let block_format = if args.progressive {
+        BlockFormat::Progressive
+    } else if args.combined {
+        BlockFormat::Combined
+    } else if args.block_offsets {
+        BlockFormat::BlocksWithOffsets
+    } else {
+        BlockFormat::Blocks
+    };
+    let mut output = BlockWriter::open(args.output.as_deref(), block_format, &config)?;
     let mut blocks_completed = 0;
     let total_blocks = config.source_blocks() as usize;
+
+    // Blocks decoded by earlier runs are written again, so that every run's output is complete
+    for block_sbn in 0..config.source_blocks() {
+        if let Some(block_data) = decoder.decoded_block(block_sbn) {
+            output.write_block(block_sbn, block_data)?;
+            blocks_completed += 1;
+        }
+    }
+    if blocks_completed == total_blocks {
+        log_info!("✓ All {} source blocks were already decoded", total_blocks);
+        remove_state(args)?;
+        return output.finish();
+    }
     
     log_info!("Starting decoding for {} source blocks...", config.source_blocks());
+    let mut last_save = Instant::now();
     
-    let mut packets_processed = 0;
-    let mut packet_buffer = vec![0u8; packet_size];
+    let mut packet_buffer = Vec::with_capacity(frame_size);
     
     loop {
-        // Try to read exactly one packet from stdin
-        match stdin.read_exact(&mut packet_buffer) {
-            Ok(()) => {
-                packets_processed += 1;
-                
-                // Deserialize the packet to get SBN
-                let packet = EncodingPacket::deserialize(&packet_buffer);
-                let payload_id = packet.payload_id();
-                let sbn = payload_id.source_block_number();
-                
-                log_info!("Received packet {} for source block {} ({} bytes)", 
-                    packets_processed, sbn, packet_size);
-                
-                // Try to decode this specific block - output immediately if it completes
-                if let Some((block_sbn, block_data)) = decoder.try_decode_block(packet) {
-                    // This block just completed! Output it immediately
-                    blocks_completed += 1;
-                    
-                    // Output: SBN (1 byte) + Block Size (4 bytes, little-endian) + block data
-                    let mut output = Vec::with_capacity(1 + 4 + block_data.len());
-                    output.push(block_sbn);
+        // Give up once the packet budget is exhausted
+        if args.max_packets.is_some_and(|max_packets| packets_processed >= max_packets) {
+            save_state(args, &decoder)?;
+            report_decode_failure("max_packets", packets_processed, corrupted_packets, &decoder);
+            bail!("Failed to decode: not all blocks could be decoded within {} packets", packets_processed);
+        }
+
+        // Wait for one packet, then also take every packet that is already buffered, so that
+        // blocks completed by the same batch are solved concurrently
+        let mut batch = mem::take(&mut first_batch);
+        let outcome = loop {
+            // The packet that carried the OTI may complete a block on its own
+            if !batch.is_empty() && stdin.buffered() < frame_size {
+                break ReadOutcome::Filled;
+            }
+
+            match layout.framing.read_packet(&mut stdin, packet_size, &mut packet_buffer) {
+                Ok(ReadOutcome::Filled) => {}
+                Ok(outcome) => break outcome,
+                Err(e) => bail!("Failed to read packet {} from stdin: {}", packets_processed + 1, e),
+            }
+            packets_processed += 1;
+
+            // Deserialize the packet to get SBN; corrupted packets are dropped before they can
+            // reach the decoder
+            match layout.parse(&packet_buffer, &config) {
+                Ok(Some(packet)) => {
+                    let sbn = packet.payload_id().source_block_number();
                     
-                    // Write block size as 4-byte little-endian u32
-                    let block_size = block_data.len() as u32;
-                    output.extend_from_slice(&block_size.to_le_bytes());
-                    output.extend_from_slice(&block_data);
+                    log_info!("Received packet {} for source block {} ({} bytes)", 
+                        packets_processed, sbn, packet_size);
                     
-                    io::stdout().write_all(&output)
-                        .context("Failed to write decoded block to stdout")?;
-                    io::stdout().flush()
-                        .context("Failed to flush stdout")?;
-                        
-                    log_info!("✓ Successfully decoded source block {} ({} bytes) using {} total packets", 
-                        block_sbn, block_data.len(), packets_processed);
-                        
-                    // Check if all blocks are now complete
-                    if blocks_completed == total_blocks {
-                        log_info!("✓ All {} source blocks completed!", total_blocks);
-                        return Ok(());
+                    // Malformed packets are skipped rather than aborting the whole decode
+                    match decoder.check_packet(&packet) {
+                        Ok(()) => batch.push(packet),
+                        Err(e) => log_info!("  → Skipping malformed packet {}: {}", packets_processed, e),
                     }
-                } else {
-                    // Block not yet complete, need more packets
-                    log_info!("  → Block {} needs more packets...", sbn);
                 }
+                Ok(None) => {
+                    corrupted_packets += 1;
+                    log_info!("  → Dropping corrupted packet {}", packets_processed);
+                }
+                Err(e) => log_info!("  → Skipping malformed packet {}: {:#}", packets_processed, e),
+            }
+
+            let budget_exhausted = args.max_packets.is_some_and(|max_packets| packets_processed >= max_packets);
+            if batch.len() >= MAX_DECODE_BATCH || budget_exhausted || stdin.buffered() < frame_size {
+                break ReadOutcome::Filled;
+            }
+        };
+
+        if args.progressive {
+            for block_sbn in decode_progressive(&mut decoder, batch, &mut output)? {
+                blocks_completed += 1;
+                log_info!("✓ Successfully decoded source block {} using {} total packets", block_sbn, packets_processed);
+            }
+        } else {
+            for block_sbn in decoder.decode_batch(batch) {
+                // This block just completed! Output it immediately
+                blocks_completed += 1;
+                // Blocks are only kept for the state file; otherwise each is dropped once written,
+                // so that no more than a batch of blocks is ever held in memory
+                let block_data = match args.state_file {
+                    Some(_) => Cow::Borrowed(decoder.decoded_block(block_sbn).unwrap()),
+                    None => Cow::Owned(decoder.take_block(block_sbn).unwrap()),
+                };
+                output.write_block(block_sbn, &block_data)?;
+
+                log_info!("✓ Successfully decoded source block {} ({} bytes with padding) using {} total packets",
+                    block_sbn, block_data.len(), packets_processed);
             }
-            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+        }
+
+        // Check if all blocks are now complete
+        if blocks_completed == total_blocks {
+            log_info!("✓ All {} source blocks completed! ({} corrupted packets dropped)", total_blocks, corrupted_packets);
+            remove_state(args)?;
+            return output.finish();
+        }
+        if last_save.elapsed() >= STATE_SAVE_INTERVAL {
+            save_state(args, &decoder)?;
+            last_save = Instant::now();
+        }
+
+        match outcome {
+            ReadOutcome::Filled => {}
+            ReadOutcome::EndOfStream => {
                 // End of stream - no more packets available
                 log_info!("End of stream reached after {} packets", packets_processed);
+                save_state(args, &decoder)?;
+                report_decode_failure("end_of_stream", packets_processed, corrupted_packets, &decoder);
                 bail!("Failed to decode: stream ended before all blocks could be decoded");
             }
-            Err(e) => {
-                bail!("Failed to read packet {} from stdin: {}", packets_processed + 1, e);
+            ReadOutcome::IdleTimeout => {
+                log_info!("No input received within idle timeout after {} packets", packets_processed);
+                save_state(args, &decoder)?;
+                report_decode_failure("idle_timeout", packets_processed, corrupted_packets, &decoder);
+                bail!("Failed to decode: no input received within idle timeout");
             }
         }
     }
 }
+
+/// Decodes a batch one packet at a time, writing every piece of the object as soon as it is known,
+/// and returns the source block numbers completed by the batch
+fn decode_progressive(decoder: &mut Decoder, batch: Vec<EncodingPacket>, output: &mut BlockWriter) -> Result<Vec<u8>> {
+    let mut completed = vec![];
+    for packet in batch {
+        let sbn = packet.payload_id().source_block_number();
+        let mut written = Ok(());
+        let block = decoder.decode_progressive(packet, |offset, data| {
+            if written.is_ok() {
+                written = output.write_piece(sbn, offset, data);
+            }
+        });
+        written?;
+        completed.extend(block);
+    }
+    Ok(completed)
+}
+
+/// Restores the decoder saved by an earlier run, which must have decoded the same object
+fn load_state(path: &Path, config: &ObjectTransmissionInformation) -> Result<Decoder> {
+    let state = fs::read(path)
+        .with_context(|| format!("Failed to read state file {}", path.display()))?;
+    let decoder = Decoder::load(&state)
+        .with_context(|| format!("Invalid state file {}", path.display()))?;
+    if decoder.get_config() != *config {
+        bail!("State file {} belongs to a different object than the stream", path.display());
+    }
+    log_info!("Resuming from state file {}", path.display());
+    Ok(decoder)
+}
+
+/// Saves the decoder to --state-file, if given, replacing the previous state only once the new
+/// one has been written in full
+fn save_state(args: &Args, decoder: &Decoder) -> Result<()> {
+    let Some(path) = &args.state_file else {
+        return Ok(());
+    };
+    let mut temporary = path.clone().into_os_string();
+    temporary.push(".tmp");
+    fs::write(&temporary, decoder.save())
+        .with_context(|| format!("Failed to write state file {}", path.display()))?;
+    fs::rename(&temporary, path)
+        .with_context(|| format!("Failed to write state file {}", path.display()))?;
+    log_info!("Saved decoder state to {}", path.display());
+    Ok(())
+}
+
+/// Removes --state-file, if given, once the object has been decoded
+fn remove_state(args: &Args) -> Result<()> {
+    match &args.state_file {
+        Some(path) => match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("Failed to remove state file {}", path.display()))
+            }
+            _ => Ok(()),
+        },
+        None => Ok(()),
+    }
+}
+
+/// Opens the encoded input: UDP datagrams for `recv`, otherwise the `--input` file or stdin
+fn open_decode_input(args: &Args) -> Result<TimedReader> {
+    let idle_timeout = args.idle_timeout.map(Duration::from_secs_f64);
+    let source: Box<dyn Read + Send> = match (&args.command, &args.input) {
+        (Some(Command::Recv(recv)), _) => Box::new(DatagramReceiver::bind(recv)?),
+        (_, Some(path)) => Box::new(File::open(path)
+            .with_context(|| format!("Failed to open input file {}", path.display()))?),
+        (_, None) => Box::new(io::stdin()),
+    };
+    Ok(TimedReader::spawn(source, idle_timeout))
+}
+
+/// Reads the OTI from the OTI header or, with --oti-per-packet, from the first intact packet,
+/// which is returned along with it
+fn read_oti(args: &Args, layout: &PacketLayout, stdin: &mut TimedReader, packets_processed: &mut u64, corrupted_packets: &mut u64) -> Result<(ObjectTransmissionInformation, Vec<EncodingPacket>)> {
+    if layout.oti_per_packet {
+        // No header: the first intact packet describes the object, and is decoded with the rest
+        let (config, packet) = read_first_packet(args, layout, stdin, packets_processed, corrupted_packets)?;
+        return Ok((config, vec![packet]));
+    }
+
+    // First, read the OTI header (12 bytes, unless shortened by --oti-*-bits) from stdin
+    let mut oti_buffer = vec![0u8; layout.oti_profile.serialized_size()];
+    read_required(stdin.read_exact(&mut oti_buffer), "OTI header")?;
+
+    // Parse ObjectTransmissionInformation (OTI) from stream
+    let config = layout.oti_profile.deserialize(&oti_buffer)
+        .context("Invalid OTI header")?;
+    Ok((config, Vec::new()))
+}
+
+/// Fails unless a read from the input filled its buffer, i.e. if the stream ended or went idle first
+fn read_required(outcome: io::Result<ReadOutcome>, what: &str) -> Result<()> {
+    match outcome.with_context(|| format!("Failed to read {} from stdin", what))? {
+        ReadOutcome::Filled => Ok(()),
+        ReadOutcome::EndOfStream => bail!("Failed to read {} from stdin: stream ended", what),
+        ReadOutcome::IdleTimeout => bail!("Failed to read {} from stdin: no input received within idle timeout", what),
+    }
+}
+
+/// Reads packets until one passes its checksum, returning the OTI it carries along with the packet
+fn read_first_packet(args: &Args, layout: &PacketLayout, stdin: &mut TimedReader, packets_processed: &mut u64, corrupted_packets: &mut u64) -> Result<(ObjectTransmissionInformation, EncodingPacket)> {
+    let oti_size = layout.embedded_oti_size();
+    let mut packet_buffer = Vec::new();
+    loop {
+        if args.max_packets.is_some_and(|max_packets| *packets_processed >= max_packets) {
+            bail!("Failed to decode: no intact packet within {} packets", packets_processed);
+        }
+
+        let config = if layout.framing == Framing::Fixed {
+            // The OTI at the start of the packet gives the size of the rest of it
+            packet_buffer.resize(oti_size, 0);
+            read_required(stdin.read_exact(&mut packet_buffer), "first packet")?;
+            let config = layout.oti_profile.deserialize(&packet_buffer)
+                .context("Invalid OTI in first packet")?;
+            packet_buffer.resize(layout.packet_size(config.symbol_size() as usize), 0);
+            read_required(stdin.read_exact(&mut packet_buffer[oti_size..]), "first packet")?;
+            *packets_processed += 1;
+            config
+        } else {
+            // Frames carry their own length, so the stream can be searched for one that starts with
+            // an OTI describing a packet of exactly that length
+            let describes_frame = |frame: &[u8]| layout.oti_profile.deserialize(frame)
+                .is_ok_and(|config| frame.len() == layout.packet_size(config.symbol_size() as usize));
+            read_required(layout.framing.read_first_packet(stdin, describes_frame, &mut packet_buffer), "first packet")?;
+            *packets_processed += 1;
+            layout.oti_profile.deserialize(&packet_buffer).unwrap()
+        };
+
+        match layout.parse(&packet_buffer, &config) {
+            Ok(Some(packet)) => return Ok((config, packet)),
+            Ok(None) => {
+                *corrupted_packets += 1;
+                log_info!("  → Dropping corrupted packet {}", packets_processed);
+            }
+            Err(e) => log_info!("  → Skipping malformed packet {}: {:#}", packets_processed, e),
+        }
+    }
+}
+
+/// Writes a single-line JSON report to stderr, listing every source block that was not recovered
+fn report_decode_failure(reason: &str, packets_processed: u64, corrupted_packets: u64, decoder: &Decoder) {
+    let unrecovered_blocks: Vec<String> = decoder.status()
+        .iter()
+        .filter(|block| !block.decoded)
+        .map(|block| format!(
+            "{{\"sbn\":{},\"source_symbols\":{},\"received_symbols\":{},\"missing_symbols\":{},\"failed_decode_attempts\":{}}}",
+            block.source_block_number,
+            block.source_symbols,
+            block.received_symbols,
+            block.missing_symbols,
+            block.failed_decode_attempts,
+        ))
+        .collect();
+
+    eprintln!(
+        "{{\"reason\":\"{}\",\"packets_processed\":{},\"corrupted_packets\":{},\"unrecovered_blocks\":[{}]}}",
+        reason,
+        packets_processed,
+        corrupted_packets,
+        unrecovered_blocks.join(","),
+    );
+}
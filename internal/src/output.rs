@@ -0,0 +1,162 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use anyhow::{Result, Context, bail};
+use memmap2::MmapMut;
+use raptorq::{ObjectTransmissionInformation, block_offsets};
+
+/// Opens the destination for encoded output: the `--output` file if given, otherwise stdout
+pub fn open_output(path: Option<&Path>) -> Result<Box<dyn Write>> {
+    match path {
+        Some(path) => {
+            let file = File::create(path)
+                .with_context(|| format!("Failed to create output file {}", path.display()))?;
+            Ok(Box::new(BufWriter::new(file)))
+        }
+        None => Ok(Box::new(io::stdout())),
+    }
+}
+
+/// How decoded source blocks are written to stdout
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BlockFormat {
+    /// Each block is written as [SBN: 1 byte][Block Size: 4 bytes LE][Block Data], in completion order
+    Blocks,
+    /// Each block is written as [SBN: 1 byte][Offset: 8 bytes LE][Length: 4 bytes LE][Block Data],
+    /// in completion order
+    BlocksWithOffsets,
+    /// The object itself: blocks are written in order as soon as every earlier block is written
+    Combined,
+    /// Pieces of blocks as soon as they are known, each written as
+    /// [SBN: 1 byte][Offset: 8 bytes LE][Length: 4 bytes LE][Data]
+    Progressive,
+}
+
+/// Destination for decoded source blocks
+pub struct BlockWriter {
+    /// Exact [start, end) range of every block within the object, excluding padding
+    ranges: Vec<(usize, usize)>,
+    destination: Destination,
+}
+
+enum Destination {
+    Stdout(BlockFormat),
+    /// Blocks that completed ahead of an earlier block, held back until they can be written in order
+    Combined {
+        pending: Vec<Option<Vec<u8>>>,
+        next_block: usize,
+    },
+    /// Each block is written at its offset in a memory-mapped file preallocated to the transfer length,
+    /// so the file holds the original object once every block has been decoded
+    File(MmapMut),
+}
+
+impl BlockWriter {
+    pub fn open(path: Option<&Path>, format: BlockFormat, config: &ObjectTransmissionInformation) -> Result<BlockWriter> {
+        let transfer_length = config.transfer_length() as usize;
+        let ranges: Vec<(usize, usize)> = block_offsets(config)
+            .into_iter()
+            .map(|(start, end)| (start, end.min(transfer_length)))
+            .collect();
+
+        let destination = match (path, format) {
+            (Some(path), _) => Destination::File(map_output_file(path, config)?),
+            (None, BlockFormat::Combined) => Destination::Combined {
+                pending: vec![None; ranges.len()],
+                next_block: 0,
+            },
+            (None, format) => Destination::Stdout(format),
+        };
+        Ok(BlockWriter { ranges, destination })
+    }
+
+    /// Writes a decoded block. Any padding past the end of the object is dropped.
+    pub fn write_block(&mut self, sbn: u8, data: &[u8]) -> Result<()> {
+        let Some(&(start, end)) = self.ranges.get(sbn as usize) else {
+            bail!("Decoded block {} is out of range", sbn);
+        };
+        let Some(data) = data.get(..end - start) else {
+            bail!("Decoded block {} is {} bytes, expected at least {}", sbn, data.len(), end - start);
+        };
+
+        match &mut self.destination {
+            Destination::Stdout(format) => {
+                let mut output = Vec::with_capacity(1 + 8 + 4 + data.len());
+                output.push(sbn);
+                if *format == BlockFormat::BlocksWithOffsets {
+                    output.extend_from_slice(&(start as u64).to_le_bytes());
+                }
+                output.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                output.extend_from_slice(data);
+                write_stdout(&output)
+            }
+            Destination::Combined { pending, next_block } => {
+                pending[sbn as usize] = Some(data.to_vec());
+                let mut stdout = io::stdout().lock();
+                while let Some(block) = pending.get_mut(*next_block).and_then(Option::take) {
+                    stdout.write_all(&block)
+                        .context("Failed to write decoded block to stdout")?;
+                    *next_block += 1;
+                }
+                stdout.flush().context("Failed to flush stdout")
+            }
+            Destination::File(map) => {
+                map[start..end].copy_from_slice(data);
+                Ok(())
+            }
+        }
+    }
+
+    /// Writes a piece of a block, at `offset` in the object, which must lie within the object
+    pub fn write_piece(&mut self, sbn: u8, offset: u64, data: &[u8]) -> Result<()> {
+        match &mut self.destination {
+            Destination::Stdout(_) => {
+                let mut output = Vec::with_capacity(1 + 8 + 4 + data.len());
+                output.push(sbn);
+                output.extend_from_slice(&offset.to_le_bytes());
+                output.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                output.extend_from_slice(data);
+                write_stdout(&output)
+            }
+            Destination::Combined { .. } => bail!("Pieces of blocks cannot be written in order"),
+            Destination::File(map) => {
+                let start = offset as usize;
+                map[start..start + data.len()].copy_from_slice(data);
+                Ok(())
+            }
+        }
+    }
+
+    /// Flushes every written block to disk
+    pub fn finish(self) -> Result<()> {
+        match self.destination {
+            Destination::File(map) => map.flush().context("Failed to flush output file"),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn write_stdout(data: &[u8]) -> Result<()> {
+    let mut stdout = io::stdout();
+    stdout.write_all(data)
+        .context("Failed to write decoded block to stdout")?;
+    stdout.flush().context("Failed to flush stdout")
+}
+
+/// Creates the output file at the transfer length and maps it into memory
+fn map_output_file(path: &Path, config: &ObjectTransmissionInformation) -> Result<MmapMut> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+        .with_context(|| format!("Failed to create output file {}", path.display()))?;
+    file.set_len(config.transfer_length())
+        .with_context(|| format!("Failed to preallocate {} bytes for {}", config.transfer_length(), path.display()))?;
+
+    // Safety: the output file is created (and truncated) by us and must not be modified by
+    // anyone else while decoding
+    unsafe { MmapMut::map_mut(&file) }
+        .with_context(|| format!("Failed to memory-map output file {}", path.display()))
+}
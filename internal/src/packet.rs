@@ -0,0 +1,133 @@
+use anyhow::{Result, Context, bail};
+use raptorq::{EncodingPacket, ObjectTransmissionInformation, OtiProfile, PayloadId, PayloadIdFormat};
+
+use crate::checksum::{Checksum, checksum_size};
+use crate::framing::Framing;
+
+/// Size of the RFC 6330 Payload ID that the encoder writes in front of every symbol
+const RFC6330_PAYLOAD_ID_SIZE: usize = 4;
+
+/// How the OTI and each encoding packet are laid out on the wire: an OTI header (unless
+/// `oti_per_packet`) followed by packets of
+/// [OTI: if oti_per_packet][PayloadId: payload_id_format bytes][Symbol: symbol_size bytes][Checksum: optional]
+/// each wrapped in a `framing` frame
+///
+/// None of this is described by the OTI, so the encoder and decoder must be given the same flags.
+#[derive(Clone, Copy)]
+pub struct PacketLayout {
+    pub oti_profile: OtiProfile,
+    pub oti_per_packet: bool,
+    pub payload_id_format: PayloadIdFormat,
+    pub checksum: Option<Checksum>,
+    pub framing: Framing,
+}
+
+impl PacketLayout {
+    fn serialize_oti(&self, config: &ObjectTransmissionInformation) -> Result<Vec<u8>> {
+        self.oti_profile.serialize(config)
+            .context("Encoding parameters do not fit the --oti-*-bits flags")
+    }
+
+    /// Serializes the OTI header written once at the start of the stream, which is empty when
+    /// every packet carries the OTI instead
+    pub fn oti_header(&self, config: &ObjectTransmissionInformation) -> Result<Vec<u8>> {
+        if self.oti_per_packet {
+            return Ok(Vec::new());
+        }
+        self.serialize_oti(config)
+    }
+
+    /// Size of the OTI at the start of every packet
+    pub fn embedded_oti_size(&self) -> usize {
+        if self.oti_per_packet { self.oti_profile.serialized_size() } else { 0 }
+    }
+
+    /// Size of a packet carrying a `symbol_size`-byte symbol
+    pub fn packet_size(&self, symbol_size: usize) -> usize {
+        self.embedded_oti_size()
+            + self.payload_id_format.serialized_size()
+            + symbol_size
+            + checksum_size(self.checksum)
+    }
+
+    /// Parses a packet in this layout, returning `None` if it fails its checksum. Fails if the
+    /// packet carries an OTI other than `config`.
+    pub fn parse(&self, packet: &[u8], config: &ObjectTransmissionInformation) -> Result<Option<EncodingPacket>> {
+        let serialized = match self.checksum {
+            Some(checksum) => match checksum.verify(packet) {
+                Some(serialized) => serialized,
+                None => return Ok(None),
+            },
+            None => packet,
+        };
+
+        if self.oti_per_packet {
+            let embedded = self.oti_profile.deserialize(serialized)
+                .context("Invalid OTI in encoding packet")?;
+            if embedded != *config {
+                bail!("Encoding packet carries the OTI of a different object");
+            }
+        }
+
+        EncodingPacket::try_deserialize_with_format(&serialized[self.embedded_oti_size()..], &self.payload_id_format)
+            .map(Some)
+            .context("Failed to parse encoding packet")
+    }
+}
+
+/// Serializes the packets of one object, one at a time, into reused buffers
+pub struct PacketWriter {
+    layout: PacketLayout,
+    oti: Vec<u8>,
+    buffer: Vec<u8>,
+    frame: Vec<u8>,
+}
+
+impl PacketWriter {
+    pub fn new(layout: &PacketLayout, config: &ObjectTransmissionInformation) -> Result<PacketWriter> {
+        let packet_size = layout.packet_size(config.symbol_size() as usize);
+        if packet_size > layout.framing.max_packet_size() {
+            bail!("{} byte packets are too long for --framing {}", packet_size, layout.framing.name());
+        }
+
+        let oti = if layout.oti_per_packet { layout.serialize_oti(config)? } else { Vec::new() };
+        let buffer = vec![0u8; oti.len() + RFC6330_PAYLOAD_ID_SIZE + config.symbol_size() as usize + checksum_size(layout.checksum)];
+        let frame = Vec::with_capacity(layout.framing.max_frame_size(packet_size));
+        Ok(PacketWriter { layout: *layout, oti, buffer, frame })
+    }
+
+    /// Buffer to pass to `write_source_packet` / `write_repair_packet`
+    pub fn packet_buffer(&mut self) -> &mut [u8] {
+        &mut self.buffer[self.oti.len()..]
+    }
+
+    /// Converts the `length`-byte RFC 6330 packet written into [`packet_buffer`](Self::packet_buffer)
+    /// into the final layout, and returns it framed.
+    ///
+    /// The compact Payload ID and the OTI are written right in front of the symbol, so the symbol
+    /// never moves.
+    pub fn finish(&mut self, length: usize) -> Result<&[u8]> {
+        let payload_id_end = self.oti.len() + RFC6330_PAYLOAD_ID_SIZE;
+        let payload_id = PayloadId::deserialize(self.buffer[self.oti.len()..payload_id_end].try_into().unwrap());
+        let payload_id_start = payload_id_end - self.layout.payload_id_format.serialized_size();
+        self.layout.payload_id_format.serialize_into(&payload_id, &mut self.buffer[payload_id_start..payload_id_end])
+            .context("Payload ID does not fit in --sbn-bits/--esi-bits")?;
+
+        let start = payload_id_start - self.oti.len();
+        self.buffer[start..payload_id_start].copy_from_slice(&self.oti);
+
+        let packet = &mut self.buffer[start..];
+        let length = self.oti.len() + length - start;
+        let length = match self.layout.checksum {
+            Some(checksum) => checksum.seal(packet, length),
+            None => length,
+        };
+        if self.layout.framing == Framing::Fixed {
+            return Ok(&packet[..length]);
+        }
+
+        self.frame.clear();
+        self.layout.framing.write_frame(&packet[..length], &mut self.frame);
+        Ok(&self.frame)
+    }
+}
@@ -0,0 +1,441 @@
+//! Persistent multi-job server mode (`--serve`)
+//!
+//! A single long-lived process reads request frames from stdin and writes response
+//! frames to stdout. Every frame is tagged with a caller-chosen job id, so any number
+//! of encodes and decodes can be interleaved over the same pair of pipes.
+//!
+//! Frame layout (both directions, integers little-endian):
+//! - Job ID (4 bytes)
+//! - Kind (1 byte)
+//! - Payload length (4 bytes)
+//! - Payload (variable length)
+//!
+//! See CLI.md for the meaning of each kind.
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::io::{self, BufWriter, Read, Write};
+use anyhow::{Result, Context, anyhow, bail};
+use raptorq::{Encoder, Decoder, EncodingPacket, ObjectTransmissionInformation};
+
+// Request kinds (stdin)
+const REQUEST_ENCODE: u8 = 0x01;
+const REQUEST_DECODE_OPEN: u8 = 0x02;
+const REQUEST_DECODE_PACKET: u8 = 0x03;
+const REQUEST_DECODE_CLOSE: u8 = 0x04;
+
+// Response kinds (stdout)
+const RESPONSE_OTI: u8 = 0x81;
+const RESPONSE_PACKET: u8 = 0x82;
+const RESPONSE_BLOCK: u8 = 0x83;
+const RESPONSE_DONE: u8 = 0x84;
+const RESPONSE_ERROR: u8 = 0x85;
+
+const FRAME_HEADER_SIZE: usize = 4 + 1 + 4;
+const ENCODE_OPTIONS_SIZE: usize = 2 + 4 + 1 + 2 + 1;
+const OTI_SIZE: usize = 12;
+// A PayloadId and a symbol of the largest symbol size
+const MAX_PACKET_SIZE: usize = 4 + u16::MAX as usize;
+// Largest object accepted by an encode request, which is buffered in full
+const MAX_ENCODE_DATA_SIZE: usize = 1 << 30;
+
+enum Request {
+    Encode {
+        config: ObjectTransmissionInformation,
+        repair_symbols: u32,
+        data: Vec<u8>,
+    },
+    DecodeOpen(ObjectTransmissionInformation),
+    DecodePacket(Vec<u8>),
+    DecodeClose,
+}
+
+struct DecodeJob {
+    config: ObjectTransmissionInformation,
+    decoder: Decoder,
+    blocks_completed: usize,
+}
+
+pub fn serve() -> Result<()> {
+    serve_jobs(io::stdin().lock(), BufWriter::new(io::stdout().lock()))
+}
+
+fn serve_jobs(mut stdin: impl Read, mut stdout: impl Write) -> Result<()> {
+    let mut decode_jobs: HashMap<u32, DecodeJob> = HashMap::new();
+
+    log_info!("Serving jobs on stdin/stdout...");
+
+    loop {
+        let mut header = [0u8; FRAME_HEADER_SIZE];
+        match stdin.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                // Clean shutdown once the client closes stdin between frames
+                log_info!("stdin closed - shutting down with {} unfinished decode jobs", decode_jobs.len());
+                return Ok(());
+            }
+            Err(e) => bail!("Failed to read request frame header from stdin: {}", e),
+        }
+
+        let job_id = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let kind = header[4];
+        let payload_length = u32::from_le_bytes([header[5], header[6], header[7], header[8]]) as usize;
+
+        let request = read_request(&mut stdin, kind, payload_length)
+            .with_context(|| format!("Failed to read {} byte payload for job {}", payload_length, job_id))?;
+        let request = match request {
+            Ok(request) => request,
+            Err(e) => {
+                // Only this request fails, and a decode job running under its id carries on. The
+                // payload has been skipped, so the next frame is intact.
+                write_frame(&mut stdout, job_id, RESPONSE_ERROR, e.to_string().as_bytes())?;
+                stdout.flush().context("Failed to flush stdout")?;
+                continue;
+            }
+        };
+
+        match request {
+            Request::Encode { config, repair_symbols, data } => {
+                encode_job(&mut stdout, job_id, config, repair_symbols, &data)?;
+            }
+            Request::DecodeOpen(config) => {
+                // Replacing the job would silently drop the packets it has collected so far
+                match decode_jobs.entry(job_id) {
+                    Entry::Occupied(_) => write_frame(&mut stdout, job_id, RESPONSE_ERROR,
+                        format!("Job {} is already decoding", job_id).as_bytes())?,
+                    Entry::Vacant(entry) => {
+                        entry.insert(DecodeJob {
+                            config,
+                            decoder: Decoder::new(config),
+                            blocks_completed: 0,
+                        });
+                    }
+                }
+            }
+            Request::DecodePacket(payload) => {
+                // Packets for unknown (or already finished) jobs are discarded, since clients
+                // typically keep feeding packets until they observe the DONE frame
+                let Some(job) = decode_jobs.get_mut(&job_id) else {
+                    log_info!("Discarding packet for unknown job {}", job_id);
+                    continue;
+                };
+
+                if decode_packet(&mut stdout, job_id, job, &payload)? {
+                    decode_jobs.remove(&job_id);
+                    write_frame(&mut stdout, job_id, RESPONSE_DONE, &[])?;
+                }
+            }
+            Request::DecodeClose => {
+                // The client has no more packets for this job
+                if decode_jobs.remove(&job_id).is_some() {
+                    write_frame(&mut stdout, job_id, RESPONSE_ERROR,
+                        b"Failed to decode: stream ended before all blocks could be decoded")?;
+                }
+            }
+        }
+
+        stdout.flush().context("Failed to flush stdout")?;
+    }
+}
+
+/// Reads the payload of a request. Requests that no valid request of their kind could look
+/// like, or encode requests for more than MAX_ENCODE_DATA_SIZE bytes, are rejected (the inner
+/// error) from their length alone, and their payload is skipped without being buffered, so that
+/// a bogus length cannot make the server allocate gigabytes. Only failing to read stdin is an
+/// outer error.
+fn read_request(stdin: &mut impl Read, kind: u8, payload_length: usize) -> io::Result<Result<Request>> {
+    let mut payload = stdin.take(payload_length as u64);
+    let request = match kind {
+        REQUEST_ENCODE if payload_length < ENCODE_OPTIONS_SIZE => Err(anyhow!(
+            "Encode request must start with {} bytes of encoding options", ENCODE_OPTIONS_SIZE)),
+        REQUEST_ENCODE if payload_length - ENCODE_OPTIONS_SIZE > MAX_ENCODE_DATA_SIZE => Err(anyhow!(
+            "Encode request of {} bytes is larger than the limit of {} bytes", payload_length - ENCODE_OPTIONS_SIZE, MAX_ENCODE_DATA_SIZE)),
+        REQUEST_ENCODE => {
+            // The options come first, so that the length of the data can be checked against them
+            let mut options = [0u8; ENCODE_OPTIONS_SIZE];
+            payload.read_exact(&mut options)?;
+            match encode_options(&options, (payload_length - ENCODE_OPTIONS_SIZE) as u64) {
+                Ok((config, repair_symbols)) => {
+                    let mut data = vec![0u8; payload_length - ENCODE_OPTIONS_SIZE];
+                    payload.read_exact(&mut data)?;
+                    Ok(Request::Encode { config, repair_symbols, data })
+                }
+                Err(e) => Err(e),
+            }
+        }
+        REQUEST_DECODE_OPEN if payload_length == OTI_SIZE => {
+            let mut oti = [0u8; OTI_SIZE];
+            payload.read_exact(&mut oti)?;
+            ObjectTransmissionInformation::try_deserialize(&oti)
+                .context("Invalid OTI")
+                .map(Request::DecodeOpen)
+        }
+        REQUEST_DECODE_OPEN => Err(anyhow!("Decode request must carry exactly {} bytes of OTI", OTI_SIZE)),
+        REQUEST_DECODE_PACKET if payload_length <= MAX_PACKET_SIZE => {
+            let mut packet = vec![0u8; payload_length];
+            payload.read_exact(&mut packet)?;
+            Ok(Request::DecodePacket(packet))
+        }
+        REQUEST_DECODE_PACKET => Err(anyhow!(
+            "Packet of {} bytes is longer than any encoding packet ({} bytes)", payload_length, MAX_PACKET_SIZE)),
+        REQUEST_DECODE_CLOSE if payload_length == 0 => Ok(Request::DecodeClose),
+        REQUEST_DECODE_CLOSE => Err(anyhow!("Decode close request must have an empty payload")),
+        _ => Err(anyhow!("Unknown request kind 0x{:02x}", kind)),
+    };
+
+    // Skip whatever a rejected request left unread, so that the next frame is read from its start
+    io::copy(&mut payload, &mut io::sink())?;
+    Ok(request)
+}
+
+fn write_frame(stdout: &mut impl Write, job_id: u32, kind: u8, payload: &[u8]) -> Result<()> {
+    let mut header = [0u8; FRAME_HEADER_SIZE];
+    header[0..4].copy_from_slice(&job_id.to_le_bytes());
+    header[4] = kind;
+    header[5..9].copy_from_slice(&(payload.len() as u32).to_le_bytes());
+
+    stdout.write_all(&header)
+        .and_then(|_| stdout.write_all(payload))
+        .with_context(|| format!("Failed to write response frame for job {}", job_id))
+}
+
+/// Parses the encoding options of an encode request for `transfer_length` bytes of data,
+/// returning the OTI and the number of repair symbols per block
+fn encode_options(options: &[u8; ENCODE_OPTIONS_SIZE], transfer_length: u64) -> Result<(ObjectTransmissionInformation, u32)> {
+    let symbol_size = u16::from_le_bytes([options[0], options[1]]);
+    let repair_symbols = u32::from_le_bytes([options[2], options[3], options[4], options[5]]);
+    let source_blocks = options[6];
+    let sub_blocks = u16::from_le_bytes([options[7], options[8]]);
+    let symbol_alignment = options[9];
+
+    // Invalid parameters must only fail this job rather than abort the whole server
+    let config = ObjectTransmissionInformation::try_new(
+        transfer_length,
+        symbol_size,
+        source_blocks,
+        sub_blocks,
+        symbol_alignment,
+    ).context("Invalid encoding parameters")?;
+    Ok((config, repair_symbols))
+}
+
+fn encode_job(stdout: &mut impl Write, job_id: u32, config: ObjectTransmissionInformation, repair_symbols: u32, data: &[u8]) -> Result<()> {
+    let encoder = Encoder::new(data, config);
+
+    write_frame(stdout, job_id, RESPONSE_OTI, &config.serialize())?;
+
+    let mut packet_buffer = vec![0u8; 4 + config.symbol_size() as usize];
+    for block_encoder in encoder.get_block_encoders() {
+        // Source packets are written straight from the block, without copying each symbol first
+        for source_symbol_id in 0..block_encoder.source_symbol_count() {
+            let length = block_encoder.write_source_packet(source_symbol_id, &mut packet_buffer);
+            write_frame(stdout, job_id, RESPONSE_PACKET, &packet_buffer[..length])?;
+        }
+        for packet in block_encoder.repair_packet_iter(0).take(repair_symbols as usize) {
+            write_frame(stdout, job_id, RESPONSE_PACKET, &packet.serialize())?;
+        }
+    }
+
+    write_frame(stdout, job_id, RESPONSE_DONE, &[])?;
+    log_info!("✓ Job {}: encoded {} bytes", job_id, data.len());
+    Ok(())
+}
+
+/// Feeds one packet into a decode job, writing out any block it completes.
+/// Returns whether every block of the job has now been decoded.
+fn decode_packet(stdout: &mut impl Write, job_id: u32, job: &mut DecodeJob, payload: &[u8]) -> Result<bool> {
+    // Malformed packets are skipped like `--decode` does, so that one of them cannot fail a job
+    // that the packets still to come may complete
+    let block = match EncodingPacket::try_deserialize(payload).and_then(|packet| job.decoder.try_decode_block(packet)) {
+        Ok(block) => block,
+        Err(e) => {
+            log_info!("  → Job {}: skipping malformed packet: {}", job_id, e);
+            return Ok(false);
+        }
+    };
+    if let Some(block) = block {
+        job.blocks_completed += 1;
+
+        // Payload: SBN (1 byte) + block data, the length is carried by the frame itself
+        let mut output = Vec::with_capacity(1 + block.data.len());
+        output.push(block.sbn);
+        output.extend_from_slice(&block.data);
+        write_frame(stdout, job_id, RESPONSE_BLOCK, &output)?;
+
+        log_info!("✓ Job {}: decoded source block {} ({} bytes)", job_id, block.sbn, block.data.len());
+    }
+
+    Ok(job.blocks_completed == job.config.source_blocks() as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Frame = (u32, u8, Vec<u8>);
+
+    fn frame(job_id: u32, kind: u8, payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![];
+        write_frame(&mut frame, job_id, kind, payload).unwrap();
+        frame
+    }
+
+    fn encode_request(job_id: u32, data: &[u8], repair_symbols: u32) -> Vec<u8> {
+        // 64 byte symbols in 2 source blocks, 1 sub-block, 8 byte alignment
+        let mut payload = vec![];
+        payload.extend_from_slice(&64u16.to_le_bytes());
+        payload.extend_from_slice(&repair_symbols.to_le_bytes());
+        payload.push(2);
+        payload.extend_from_slice(&1u16.to_le_bytes());
+        payload.push(8);
+        payload.extend_from_slice(data);
+        frame(job_id, REQUEST_ENCODE, &payload)
+    }
+
+    fn data(length: usize, seed: usize) -> Vec<u8> {
+        (0..length).map(|i| (i * 31 + seed) as u8).collect()
+    }
+
+    fn run(input: &[u8]) -> Vec<Frame> {
+        let mut output = vec![];
+        serve_jobs(input, &mut output).unwrap();
+
+        let mut frames = vec![];
+        let mut rest = &output[..];
+        while !rest.is_empty() {
+            let job_id = u32::from_le_bytes(rest[..4].try_into().unwrap());
+            let length = u32::from_le_bytes(rest[5..9].try_into().unwrap()) as usize;
+            frames.push((job_id, rest[4], rest[FRAME_HEADER_SIZE..FRAME_HEADER_SIZE + length].to_vec()));
+            rest = &rest[FRAME_HEADER_SIZE + length..];
+        }
+        frames
+    }
+
+    /// Encodes `data` through the server, returning its OTI and packets
+    fn encode(data: &[u8]) -> (Vec<u8>, Vec<Vec<u8>>) {
+        let frames = run(&encode_request(1, data, 10));
+        assert_eq!(frames[0].1, RESPONSE_OTI);
+        assert_eq!(frames.last().unwrap().1, RESPONSE_DONE);
+        let packets = frames[1..frames.len() - 1].iter()
+            .inspect(|(_, kind, _)| assert_eq!(*kind, RESPONSE_PACKET))
+            .map(|(_, _, packet)| packet.clone())
+            .collect();
+        (frames[0].2.clone(), packets)
+    }
+
+    /// Reassembles the blocks a job decoded, checking that it finished with a DONE frame
+    fn decoded(frames: &[Frame], job_id: u32) -> Vec<u8> {
+        let job_frames: Vec<_> = frames.iter().filter(|(id, _, _)| *id == job_id).collect();
+        assert_eq!(job_frames.last().unwrap().1, RESPONSE_DONE);
+        let mut blocks: Vec<_> = job_frames[..job_frames.len() - 1].iter()
+            .inspect(|(_, kind, _)| assert_eq!(*kind, RESPONSE_BLOCK))
+            .map(|(_, _, block)| block)
+            .collect();
+        blocks.sort_by_key(|block| block[0]);
+        blocks.iter().flat_map(|block| block[1..].to_vec()).collect()
+    }
+
+    fn errors(frames: &[Frame]) -> Vec<u32> {
+        frames.iter().filter(|(_, kind, _)| *kind == RESPONSE_ERROR).map(|(id, _, _)| *id).collect()
+    }
+
+    #[test]
+    fn encode_then_decode_round_trip() {
+        let data = data(3000, 0);
+        let (oti, packets) = encode(&data);
+
+        // Lose every fifth packet, the repair packets make up for them
+        let mut input = frame(7, REQUEST_DECODE_OPEN, &oti);
+        for (i, packet) in packets.iter().enumerate() {
+            if i % 5 != 0 {
+                input.extend(frame(7, REQUEST_DECODE_PACKET, packet));
+            }
+        }
+        let frames = run(&input);
+        assert!(errors(&frames).is_empty());
+        assert_eq!(decoded(&frames, 7), data);
+    }
+
+    #[test]
+    fn interleaved_jobs() {
+        let (first, second) = (data(3000, 1), data(2000, 2));
+        let (first_oti, first_packets) = encode(&first);
+        let (second_oti, second_packets) = encode(&second);
+
+        let mut input = frame(1, REQUEST_DECODE_OPEN, &first_oti);
+        input.extend(frame(2, REQUEST_DECODE_OPEN, &second_oti));
+        for i in 0..first_packets.len().max(second_packets.len()) {
+            if let Some(packet) = first_packets.get(i) {
+                input.extend(frame(1, REQUEST_DECODE_PACKET, packet));
+            }
+            if i == 5 {
+                input.extend(encode_request(3, &first, 0));
+            }
+            if let Some(packet) = second_packets.get(i) {
+                input.extend(frame(2, REQUEST_DECODE_PACKET, packet));
+            }
+        }
+        let frames = run(&input);
+        assert!(errors(&frames).is_empty());
+        assert_eq!(decoded(&frames, 1), first);
+        assert_eq!(decoded(&frames, 2), second);
+        assert_eq!(frames.iter().rfind(|(id, _, _)| *id == 3).unwrap().1, RESPONSE_DONE);
+    }
+
+    #[test]
+    fn rejected_requests_leave_running_job() {
+        let data = data(3000, 3);
+        let (oti, packets) = encode(&data);
+        let (first, rest) = packets.split_at(packets.len() / 2);
+
+        let mut input = frame(1, REQUEST_DECODE_OPEN, &oti);
+        input.extend(first.iter().flat_map(|packet| frame(1, REQUEST_DECODE_PACKET, packet)));
+        // A duplicate open, a short OTI, an unknown kind, an oversized packet and a non-empty
+        // close are each answered with an error frame
+        input.extend(frame(1, REQUEST_DECODE_OPEN, &oti));
+        input.extend(frame(1, REQUEST_DECODE_OPEN, &oti[..OTI_SIZE - 1]));
+        input.extend(frame(1, 0x7f, b"bogus"));
+        input.extend(frame(1, REQUEST_DECODE_PACKET, &vec![0; MAX_PACKET_SIZE + 1]));
+        input.extend(frame(1, REQUEST_DECODE_CLOSE, &[0]));
+        // A packet with the wrong symbol length and one for a block that does not exist are
+        // skipped without a reply
+        input.extend(frame(1, REQUEST_DECODE_PACKET, &rest[0][..20]));
+        input.extend(frame(1, REQUEST_DECODE_PACKET, &[&[9, 0, 0, 0][..], &[0; 64]].concat()));
+        input.extend(rest.iter().flat_map(|packet| frame(1, REQUEST_DECODE_PACKET, packet)));
+
+        let frames = run(&input);
+        assert_eq!(errors(&frames), vec![1; 5]);
+        let frames: Vec<_> = frames.into_iter().filter(|(_, kind, _)| *kind != RESPONSE_ERROR).collect();
+        assert_eq!(decoded(&frames, 1), data);
+    }
+
+    #[test]
+    fn oversized_encode_is_rejected_without_buffering() {
+        // The header claims far more than the limit, but the client only sends the options
+        let mut input = vec![];
+        input.extend_from_slice(&5u32.to_le_bytes());
+        input.push(REQUEST_ENCODE);
+        input.extend_from_slice(&u32::MAX.to_le_bytes());
+        input.extend_from_slice(&encode_request(5, &[], 0)[FRAME_HEADER_SIZE..]);
+
+        let frames = run(&input);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(errors(&frames), vec![5]);
+    }
+
+    #[test]
+    fn close_fails_unfinished_job() {
+        let (oti, packets) = encode(&data(3000, 4));
+
+        let mut input = frame(1, REQUEST_DECODE_OPEN, &oti);
+        input.extend(frame(1, REQUEST_DECODE_PACKET, &packets[0]));
+        input.extend(frame(1, REQUEST_DECODE_CLOSE, &[]));
+        // The job is gone, so its packets are discarded and closing it again is a no-op
+        input.extend(packets.iter().flat_map(|packet| frame(1, REQUEST_DECODE_PACKET, packet)));
+        input.extend(frame(1, REQUEST_DECODE_CLOSE, &[]));
+
+        let frames = run(&input);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(errors(&frames), vec![1]);
+    }
+}
@@ -0,0 +1,370 @@
+//! Packet-loss channel simulator (`simulate`)
+//!
+//! Encodes a random object once, then sends its packets through a simulated erasure channel over
+//! and over, decoding every source block from whatever survives. This measures how likely each
+//! block and the whole object are to decode, and how many packets beyond K decoding took, for a
+//! given OTI and repair symbol count.
+//!
+//! Packets go out in the same order as with `--encode`: block by block, source packets first.
+
+use std::iter;
+use std::time::{Duration, Instant};
+use anyhow::{Result, bail};
+use clap::{Args, ValueEnum};
+use rayon::prelude::*;
+use raptorq::{EncodingPacket, ObjectTransmissionInformation, SourceBlockDecoder, SourceBlockEncoder, block_offsets};
+
+// Extra packets beyond K are counted individually up to this many, and lumped together above
+const OVERHEAD_BUCKETS: usize = 4;
+
+/// How packets are lost in the simulated channel (`--loss-model`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LossModel {
+    /// Every packet is lost independently with probability --loss
+    Bernoulli,
+    /// Two-state Markov chain losing every packet in the bad state, with an average loss of
+    /// --loss in bursts of --burst-length packets on average
+    GilbertElliott,
+    /// --outage-length consecutive packets lost every --outage-period packets, at a random phase
+    Outage,
+}
+
+#[derive(Args)]
+pub struct SimulateArgs {
+    #[arg(long, value_enum, default_value = "bernoulli", help = "How packets are lost")]
+    loss_model: LossModel,
+
+    #[arg(long, default_value = "0.1", help = "Average fraction of packets lost (bernoulli, gilbert-elliott)")]
+    loss: f64,
+
+    #[arg(long, default_value = "5", help = "Average number of consecutive packets lost in a burst (gilbert-elliott)")]
+    burst_length: f64,
+
+    #[arg(long, default_value = "100", help = "Packets from the start of one outage to the start of the next (outage)")]
+    outage_period: u64,
+
+    #[arg(long, default_value = "10", help = "Consecutive packets lost in each outage (outage)")]
+    outage_length: u64,
+
+    #[arg(long, default_value = "100", help = "Number of times the object is sent through the channel")]
+    trials: u32,
+
+    #[arg(long, default_value = "0", help = "Seed for the object contents and the losses, so that runs can be repeated")]
+    seed: u64,
+
+    #[arg(long, help = "Print the report as a single line of JSON")]
+    json: bool,
+}
+
+/// SplitMix64, which is plenty for simulated losses and keeps runs reproducible
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+enum Channel {
+    Bernoulli { loss: f64 },
+    GilbertElliott { to_bad: f64, to_good: f64, bad: bool },
+    Outage { period: u64, length: u64, position: u64 },
+}
+
+impl Channel {
+    /// Starts the channel in a random state, distributed as in the long run
+    fn new(args: &SimulateArgs, rng: &mut Rng) -> Channel {
+        match args.loss_model {
+            LossModel::Bernoulli => Channel::Bernoulli { loss: args.loss },
+            LossModel::GilbertElliott => {
+                let to_good = 1.0 / args.burst_length;
+                Channel::GilbertElliott {
+                    to_bad: args.loss * to_good / (1.0 - args.loss),
+                    to_good,
+                    bad: rng.next_f64() < args.loss,
+                }
+            }
+            LossModel::Outage => Channel::Outage {
+                period: args.outage_period,
+                length: args.outage_length,
+                position: rng.next_u64() % args.outage_period,
+            },
+        }
+    }
+
+    /// Whether the next packet is lost
+    fn drops(&mut self, rng: &mut Rng) -> bool {
+        match self {
+            Channel::Bernoulli { loss } => rng.next_f64() < *loss,
+            Channel::GilbertElliott { to_bad, to_good, bad } => {
+                let dropped = *bad;
+                let switch = if *bad { *to_good } else { *to_bad };
+                if rng.next_f64() < switch {
+                    *bad = !*bad;
+                }
+                dropped
+            }
+            Channel::Outage { period, length, position } => {
+                let dropped = *position < *length;
+                *position = (*position + 1) % *period;
+                dropped
+            }
+        }
+    }
+}
+
+fn validate(args: &SimulateArgs) -> Result<()> {
+    if args.trials == 0 {
+        bail!("Number of trials must be greater than 0");
+    }
+    match args.loss_model {
+        LossModel::Bernoulli => {
+            if !(0.0..=1.0).contains(&args.loss) {
+                bail!("Loss must be between 0 and 1");
+            }
+        }
+        LossModel::GilbertElliott => {
+            if !(0.0..1.0).contains(&args.loss) {
+                bail!("Loss must be at least 0 and less than 1 for gilbert-elliott");
+            }
+            if !(args.burst_length >= 1.0 && args.burst_length.is_finite()) {
+                bail!("Burst length must be at least 1 packet");
+            }
+            // The good runs between bursts must average at least one packet
+            if args.loss / (1.0 - args.loss) > args.burst_length {
+                bail!("Loss of {} cannot be reached with bursts of {} packets", args.loss, args.burst_length);
+            }
+        }
+        LossModel::Outage => {
+            if args.outage_period == 0 || args.outage_length > args.outage_period {
+                bail!("Outage length must not exceed a non-zero outage period");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// What happened to one source block in one trial
+struct BlockOutcome {
+    source_symbols: u32,
+    received: u32,
+    /// Packets handed to the decoder by the time it succeeded
+    needed: Option<u32>,
+    decode_time: Duration,
+}
+
+struct TrialOutcome {
+    sent: u64,
+    dropped: u64,
+    blocks: Vec<BlockOutcome>,
+}
+
+fn run_trial(args: &SimulateArgs, config: &ObjectTransmissionInformation, blocks: &[(u64, Vec<EncodingPacket>)], seed: u64) -> TrialOutcome {
+    let mut rng = Rng(seed);
+    let mut channel = Channel::new(args, &mut rng);
+    let mut outcome = TrialOutcome { sent: 0, dropped: 0, blocks: Vec::with_capacity(blocks.len()) };
+
+    for (sbn, (block_length, packets)) in blocks.iter().enumerate() {
+        let mut decoder = SourceBlockDecoder::new(sbn as u8, config, *block_length);
+        let source_symbols = decoder.status().source_symbols;
+        let mut block = BlockOutcome { source_symbols, received: 0, needed: None, decode_time: Duration::ZERO };
+
+        // Every packet goes through the channel, even once the block has been decoded, so that
+        // the channel state carries over to the next block as it would on the wire
+        for packet in packets {
+            outcome.sent += 1;
+            if channel.drops(&mut rng) {
+                outcome.dropped += 1;
+                continue;
+            }
+            block.received += 1;
+            if block.needed.is_none() {
+                let start = Instant::now();
+                let decoded = decoder.decode(iter::once(packet.clone())).is_some();
+                block.decode_time += start.elapsed();
+                if decoded {
+                    block.needed = Some(block.received);
+                }
+            }
+        }
+        outcome.blocks.push(block);
+    }
+    outcome
+}
+
+/// Runs the simulation for `config`, with `repair_symbols` repair packets sent per block
+pub fn simulate(args: &SimulateArgs, config: ObjectTransmissionInformation, repair_symbols: impl Fn(&SourceBlockEncoder) -> Result<u32>) -> Result<()> {
+    validate(args)?;
+
+    // Random contents, zero-padded to whole symbols so that every block is a plain slice
+    let offsets = block_offsets(&config);
+    let mut rng = Rng(args.seed);
+    let mut data: Vec<u8> = (0..config.transfer_length()).map(|_| rng.next_u64() as u8).collect();
+    data.resize(offsets.last().map_or(0, |&(_, end)| end), 0);
+
+    let mut encode_time = Duration::ZERO;
+    let mut blocks = Vec::with_capacity(offsets.len());
+    for (sbn, &(start, end)) in offsets.iter().enumerate() {
+        let begin = Instant::now();
+        let block_encoder = SourceBlockEncoder::new(sbn as u8, &config, &data[start..end]);
+        let mut packets = block_encoder.source_packets();
+        packets.extend(block_encoder.repair_packets(0, repair_symbols(&block_encoder)?));
+        encode_time += begin.elapsed();
+        blocks.push(((end - start) as u64, packets));
+    }
+
+    // Every trial has its own seed, so results do not depend on the number of threads
+    let trials: Vec<TrialOutcome> = (0..args.trials as u64)
+        .into_par_iter()
+        .map(|trial| run_trial(args, &config, &blocks, args.seed.wrapping_add(trial + 1).wrapping_mul(0x2545_f491_4f6c_dd1d)))
+        .collect();
+
+    let report = Report::new(args, &trials, encode_time / blocks.len() as u32);
+    if args.json {
+        println!("{}", report.json());
+    } else {
+        print!("{}", report.text());
+    }
+    Ok(())
+}
+
+struct Report<'a> {
+    args: &'a SimulateArgs,
+    blocks: usize,
+    packets_per_trial: u64,
+    observed_loss: f64,
+    block_success: f64,
+    object_success: f64,
+    /// Blocks that could not decode because fewer than K packets arrived
+    short_blocks: u64,
+    /// Blocks that received at least K packets but still could not be decoded
+    unsolved_blocks: u64,
+    mean_overhead: f64,
+    max_overhead: u32,
+    overhead_histogram: [u64; OVERHEAD_BUCKETS],
+    encode_time_per_block: Duration,
+    decode_time_per_block: Duration,
+}
+
+impl<'a> Report<'a> {
+    fn new(args: &'a SimulateArgs, trials: &[TrialOutcome], encode_time_per_block: Duration) -> Report<'a> {
+        let blocks = trials[0].blocks.len();
+        let total_blocks = (trials.len() * blocks) as u64;
+        let sent: u64 = trials.iter().map(|trial| trial.sent).sum();
+        let dropped: u64 = trials.iter().map(|trial| trial.dropped).sum();
+        let outcomes = || trials.iter().flat_map(|trial| trial.blocks.iter());
+
+        let mut decoded_blocks = 0;
+        let mut short_blocks = 0;
+        let mut unsolved_blocks = 0;
+        let mut total_overhead = 0u64;
+        let mut max_overhead = 0;
+        let mut overhead_histogram = [0u64; OVERHEAD_BUCKETS];
+        let mut decode_time = Duration::ZERO;
+        for block in outcomes() {
+            decode_time += block.decode_time;
+            match block.needed {
+                Some(needed) => {
+                    let overhead = needed - block.source_symbols;
+                    decoded_blocks += 1;
+                    total_overhead += overhead as u64;
+                    max_overhead = max_overhead.max(overhead);
+                    overhead_histogram[(overhead as usize).min(OVERHEAD_BUCKETS - 1)] += 1;
+                }
+                None if block.received < block.source_symbols => short_blocks += 1,
+                None => unsolved_blocks += 1,
+            }
+        }
+        let decoded_objects = trials.iter()
+            .filter(|trial| trial.blocks.iter().all(|block| block.needed.is_some()))
+            .count();
+
+        Report {
+            args,
+            blocks,
+            packets_per_trial: trials[0].sent,
+            observed_loss: dropped as f64 / sent as f64,
+            block_success: decoded_blocks as f64 / total_blocks as f64,
+            object_success: decoded_objects as f64 / trials.len() as f64,
+            short_blocks,
+            unsolved_blocks,
+            mean_overhead: if decoded_blocks > 0 { total_overhead as f64 / decoded_blocks as f64 } else { 0.0 },
+            max_overhead,
+            overhead_histogram,
+            encode_time_per_block,
+            decode_time_per_block: decode_time / total_blocks as u32,
+        }
+    }
+
+    fn text(&self) -> String {
+        let histogram: Vec<String> = self.overhead_histogram.iter()
+            .enumerate()
+            .map(|(overhead, count)| {
+                let plus = if overhead == OVERHEAD_BUCKETS - 1 { "+" } else { "" };
+                format!("{}{}: {}", overhead, plus, count)
+            })
+            .collect();
+
+        format!(
+            "trials:                 {}\n\
+             source blocks:          {}\n\
+             packets per trial:      {}\n\
+             loss model:             {}\n\
+             observed loss:          {:.4}\n\
+             block success rate:     {:.4}\n\
+             object success rate:    {:.4}\n\
+             blocks short of K:      {}\n\
+             blocks unsolved at K+:  {}\n\
+             overhead beyond K:      mean {:.3}, max {}, histogram {}\n\
+             encode time per block:  {:.3} ms\n\
+             decode time per block:  {:.3} ms\n",
+            self.args.trials,
+            self.blocks,
+            self.packets_per_trial,
+            self.args.loss_model.to_possible_value().unwrap().get_name(),
+            self.observed_loss,
+            self.block_success,
+            self.object_success,
+            self.short_blocks,
+            self.unsolved_blocks,
+            self.mean_overhead,
+            self.max_overhead,
+            histogram.join(", "),
+            self.encode_time_per_block.as_secs_f64() * 1000.0,
+            self.decode_time_per_block.as_secs_f64() * 1000.0,
+        )
+    }
+
+    fn json(&self) -> String {
+        let histogram: Vec<String> = self.overhead_histogram.iter().map(u64::to_string).collect();
+        format!(
+            "{{\"trials\":{},\"source_blocks\":{},\"packets_per_trial\":{},\"loss_model\":\"{}\",\"observed_loss\":{},\
+             \"block_success_rate\":{},\"object_success_rate\":{},\"blocks_short_of_k\":{},\"blocks_unsolved\":{},\
+             \"mean_overhead\":{},\"max_overhead\":{},\"overhead_histogram\":[{}],\
+             \"encode_ms_per_block\":{},\"decode_ms_per_block\":{}}}",
+            self.args.trials,
+            self.blocks,
+            self.packets_per_trial,
+            self.args.loss_model.to_possible_value().unwrap().get_name(),
+            self.observed_loss,
+            self.block_success,
+            self.object_success,
+            self.short_blocks,
+            self.unsolved_blocks,
+            self.mean_overhead,
+            self.max_overhead,
+            histogram.join(","),
+            self.encode_time_per_block.as_secs_f64() * 1000.0,
+            self.decode_time_per_block.as_secs_f64() * 1000.0,
+        )
+    }
+}
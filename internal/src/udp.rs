@@ -0,0 +1,242 @@
+//! UDP transport for the `send` and `recv` subcommands
+//!
+//! Every encoding packet travels in a datagram of its own, and always carries the OTI (in the
+//! `--oti-*` layout), so the receiver can start decoding from whichever datagram arrives first.
+//! Datagram boundaries delimit the packets, so `--framing` does not apply.
+
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::thread;
+use std::time::{Duration, Instant};
+use anyhow::{Result, Context, bail};
+use clap::Args;
+
+use crate::framing::Framing;
+
+// Largest UDP payload
+const MAX_DATAGRAM_SIZE: usize = 65535;
+
+#[derive(Args)]
+pub struct SendArgs {
+    #[arg(long, value_name = "ADDR", help = "Send datagrams to this host:port, which may be a multicast group")]
+    to: String,
+
+    #[arg(long, value_name = "ADDR", help = "Send from this local address (default: any address of the destination's family, any port)")]
+    bind: Option<SocketAddr>,
+
+    #[arg(long, conflicts_with = "bit_rate", help = "Pace sending to at most this many packets per second")]
+    packet_rate: Option<f64>,
+
+    #[arg(long, help = "Pace sending to at most this many bits of UDP payload per second")]
+    bit_rate: Option<f64>,
+
+    #[arg(long, default_value = "1", help = "Time-to-live of datagrams sent to an IPv4 multicast group")]
+    multicast_ttl: u32,
+}
+
+#[derive(Args)]
+pub struct RecvArgs {
+    #[arg(long, value_name = "ADDR", help = "Receive datagrams on this local address:port")]
+    bind: SocketAddr,
+
+    #[arg(long, value_name = "ADDR", help = "Join this multicast group")]
+    multicast_group: Option<IpAddr>,
+
+    #[arg(long, value_name = "ADDR", default_value = "0.0.0.0", help = "Local interface to join an IPv4 multicast group on (default: chosen by the system)")]
+    multicast_interface: Ipv4Addr,
+}
+
+/// Sends every write as one datagram, pacing writes to the configured rate
+///
+/// Packets must therefore be written with a single `write_all` each; empty writes are ignored.
+pub struct DatagramSender {
+    socket: UdpSocket,
+    destination: SocketAddr,
+    packet_rate: Option<f64>,
+    bit_rate: Option<f64>,
+    next_send: Instant,
+}
+
+impl DatagramSender {
+    pub fn open(args: &SendArgs) -> Result<DatagramSender> {
+        let destination = args.to.to_socket_addrs()
+            .with_context(|| format!("Failed to resolve {}", args.to))?
+            .next()
+            .with_context(|| format!("{} did not resolve to any address", args.to))?;
+        for rate in [args.packet_rate, args.bit_rate].into_iter().flatten() {
+            if !(rate.is_finite() && rate > 0.0) {
+                bail!("Send rate must be a positive number");
+            }
+        }
+
+        let local = args.bind.unwrap_or(match destination {
+            SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => "[::]:0".parse().unwrap(),
+        });
+        let socket = UdpSocket::bind(local)
+            .with_context(|| format!("Failed to bind UDP socket to {}", local))?;
+        if let IpAddr::V4(group) = destination.ip() {
+            if group.is_multicast() {
+                socket.set_multicast_ttl_v4(args.multicast_ttl)
+                    .context("Failed to set multicast TTL")?;
+            }
+        }
+
+        log_info!("Sending datagrams from {} to {}", socket.local_addr()?, destination);
+        Ok(DatagramSender {
+            socket,
+            destination,
+            packet_rate: args.packet_rate,
+            bit_rate: args.bit_rate,
+            next_send: Instant::now(),
+        })
+    }
+
+    /// Sleeps until the next datagram may go out, then reserves the time it takes up at the
+    /// configured rate. Falling behind does not earn a burst later on.
+    fn pace(&mut self, size: usize) {
+        let interval = match (self.packet_rate, self.bit_rate) {
+            (Some(packet_rate), _) => 1.0 / packet_rate,
+            (None, Some(bit_rate)) => (size * 8) as f64 / bit_rate,
+            (None, None) => return,
+        };
+
+        let now = Instant::now();
+        if self.next_send > now {
+            thread::sleep(self.next_send - now);
+        } else {
+            self.next_send = now;
+        }
+        self.next_send += Duration::from_secs_f64(interval);
+    }
+}
+
+impl Write for DatagramSender {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        self.pace(buf.len());
+        self.socket.send_to(buf, self.destination)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Receives datagrams, and hands each one out whole from a single read, so that the decoder can
+/// take it as a packet (see [`Framing::Datagram`]) and drop datagrams of the wrong size whole
+///
+/// Reads must therefore have room for the largest datagram; the rest of a datagram is lost.
+pub struct DatagramReceiver {
+    socket: UdpSocket,
+}
+
+impl DatagramReceiver {
+    /// Framing of the stream read from the receiver
+    pub const FRAMING: Framing = Framing::Datagram;
+
+    pub fn bind(args: &RecvArgs) -> Result<DatagramReceiver> {
+        let socket = UdpSocket::bind(args.bind)
+            .with_context(|| format!("Failed to bind UDP socket to {}", args.bind))?;
+        match args.multicast_group {
+            Some(IpAddr::V4(group)) => socket.join_multicast_v4(&group, &args.multicast_interface)
+                .with_context(|| format!("Failed to join multicast group {}", group))?,
+            Some(IpAddr::V6(group)) => socket.join_multicast_v6(&group, 0)
+                .with_context(|| format!("Failed to join multicast group {}", group))?,
+            None => {}
+        }
+
+        log_info!("Receiving datagrams on {}", socket.local_addr()?);
+        Ok(DatagramReceiver { socket })
+    }
+}
+
+impl Read for DatagramReceiver {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        debug_assert!(buf.len() >= MAX_DATAGRAM_SIZE, "read buffer cannot hold every datagram");
+        loop {
+            // An empty read would mean the end of the stream, so empty datagrams are skipped
+            let size = self.socket.recv(buf)?;
+            if size > 0 {
+                return Ok(size);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use raptorq::{Decoder, Encoder, ObjectTransmissionInformation, OtiProfile, PayloadIdFormat};
+
+    use super::*;
+    use crate::checksum::Checksum;
+    use crate::input::{ReadOutcome, TimedReader};
+    use crate::packet::{PacketLayout, PacketWriter};
+
+    #[test]
+    fn datagrams_decode_over_loopback() {
+        let receiver = DatagramReceiver::bind(&RecvArgs {
+            bind: (Ipv4Addr::LOCALHOST, 0).into(),
+            multicast_group: None,
+            multicast_interface: Ipv4Addr::UNSPECIFIED,
+        }).unwrap();
+        let mut sender = DatagramSender::open(&SendArgs {
+            to: receiver.socket.local_addr().unwrap().to_string(),
+            bind: None,
+            packet_rate: None,
+            bit_rate: None,
+            multicast_ttl: 1,
+        }).unwrap();
+
+        let data: Vec<u8> = (0..5000).map(|i| (i * 31 % 251) as u8).collect();
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 2, 1, 8);
+        let layout = |framing| PacketLayout {
+            oti_profile: OtiProfile::RFC6330,
+            oti_per_packet: true,
+            payload_id_format: PayloadIdFormat::RFC6330,
+            checksum: Some(Checksum::Crc32c),
+            framing,
+        };
+        let sending = layout(Framing::Fixed);
+        let receiving = layout(DatagramReceiver::FRAMING);
+
+        // A datagram too short to be a packet, and one of the right size that fails its checksum,
+        // ahead of the packets of every block but its first source packet
+        sender.write_all(&[0x42; 7]).unwrap();
+        sender.write_all(&vec![0x42; sending.packet_size(64)]).unwrap();
+        let mut writer = PacketWriter::new(&sending, &config).unwrap();
+        for block_encoder in Encoder::new(&data, config).get_block_encoders() {
+            for esi in 1..block_encoder.source_symbol_count() {
+                let length = block_encoder.write_source_packet(esi, writer.packet_buffer());
+                sender.write_all(writer.finish(length).unwrap()).unwrap();
+            }
+            for repair_symbol_id in 0..5 {
+                let length = block_encoder.write_repair_packet(repair_symbol_id, writer.packet_buffer());
+                sender.write_all(writer.finish(length).unwrap()).unwrap();
+            }
+        }
+
+        let mut reader = TimedReader::spawn(receiver, Some(Duration::from_secs(5)));
+        let mut decoder = Decoder::new(config);
+        let mut datagram = vec![];
+        let mut corrupted = 0;
+        let decoded = loop {
+            match receiving.framing.read_packet(&mut reader, receiving.packet_size(64), &mut datagram).unwrap() {
+                ReadOutcome::Filled => {}
+                _ => panic!("datagrams stopped before the object was decoded"),
+            }
+            match receiving.parse(&datagram, &config).unwrap() {
+                Some(packet) => {
+                    if let Some(decoded) = decoder.decode(packet) {
+                        break decoded;
+                    }
+                }
+                None => corrupted += 1,
+            }
+        };
+        assert_eq!(corrupted, 1);
+        assert_eq!(decoded, data);
+    }
+}
@@ -0,0 +1,109 @@
+import { throw_error } from "../uoe/throw_error.js";
+import { error_user_payload } from "../uoe/error_user_payload.js";
+
+// Buffers handed over by the addon are exposed as plain Uint8Array views, like the bytes read from the binary
+const as_uint8_array = (buffer) => new Uint8Array(buffer.buffer, buffer.byteOffset, buffer.length);
+
+const decode_blocks = ({ addon }, input) => {
+	const blocks = {
+		async *[Symbol.asyncIterator]() {
+			let decoder;
+
+			try {
+				decoder = new addon.Decoder(input.oti);
+			} catch (e) {
+				throw new Error(`RaptorQ decoding error: ${e.message}`);
+			}
+
+			const num_source_blocks = input.oti[8]; // Number of Source Blocks (Z)
+			let num_decoded_blocks = 0;
+
+			for await (const symbol of input.encoding_packets) {
+				if (!(symbol instanceof Uint8Array)) {
+					throw new Error('Each symbol must be a Uint8Array');
+				}
+
+				let block;
+
+				try {
+					block = decoder.decode_block(symbol);
+				} catch (e) {
+					// Malformed packets are skipped, just like the binary skips them
+					continue;
+				}
+
+				if (block === null) {
+					continue;
+				}
+
+				yield {
+					sbn: BigInt(block.sbn),
+					data: as_uint8_array(block.data),
+				};
+
+				// Stop consuming packets once every block is decoded, like the binary exiting early
+				num_decoded_blocks++;
+				if (num_decoded_blocks === num_source_blocks) {
+					return;
+				}
+			}
+
+			throw new Error("RaptorQ decoding error: stream ended before all blocks could be decoded");
+		}
+	};
+
+	return { blocks };
+};
+
+const decode_combined = async ({ addon }, input) => {
+	const blocks_map = new Map();
+	for await (const block of decode_blocks({ addon }, input).blocks) {
+		blocks_map.set(block.sbn, block.data);
+	}
+
+	// Sort blocks by SBN and combine
+	const sorted_sbns = Array.from(blocks_map.keys()).sort((a, b) => Number(a - b));
+	const combined_blocks = sorted_sbns.map(sbn => blocks_map.get(sbn));
+
+	const total_length = combined_blocks.reduce((sum, block) => sum + block.length, 0);
+	const result = new Uint8Array(total_length);
+	let offset = 0;
+
+	for (const block of combined_blocks) {
+		result.set(block, offset);
+		offset += block.length;
+	}
+
+	return result;
+};
+
+export const decode_addon = ({ addon }, { usage, oti, encoding_packets }) => {
+	usage ??= {};
+	usage.output_format ??= "combined";
+
+	if (false
+		|| !(oti instanceof Uint8Array)
+		|| oti.length !== 12
+	) {
+		throw_error(error_user_payload("Provided oti must be 12-byte Uint8Array."));
+	}
+
+	if (false
+		|| !encoding_packets
+		|| typeof encoding_packets[Symbol.asyncIterator] !== "function"
+	) {
+		throw_error(error_user_payload("Provided encoding_packets must be iterable."));
+	}
+
+	if (false
+		|| !["combined", "blocks"].includes(usage.output_format)
+	) {
+		throw_error(error_user_payload("Provided output_format must be \"combined\" or \"blocks\"."));
+	}
+
+	if (usage.output_format === "blocks") {
+		return decode_blocks({ addon }, { oti, encoding_packets });
+	} else {
+		return decode_combined({ addon }, { oti, encoding_packets });
+	}
+};
@@ -0,0 +1,55 @@
+import { throw_error } from "../uoe/throw_error.js";
+import { error_user_payload } from "../uoe/error_user_payload.js";
+import { exact_options } from "./exact_options.js";
+
+// Buffers handed over by the addon are exposed as plain Uint8Array views, like the bytes read from the binary
+const as_uint8_array = (buffer) => new Uint8Array(buffer.buffer, buffer.byteOffset, buffer.length);
+
+export const encode_addon = ({ addon }, { options, data }) => {
+	if (!(data instanceof Uint8Array)) {
+		throw_error(error_user_payload("Provided data must be Uint8Array."));
+	}
+
+	if (data.length < 1) {
+		throw_error(error_user_payload("Provided data must be non-empty."));
+	}
+
+	options = exact_options(options);
+
+	// Encoding happens in-process and up front, so parameters the encoder rejects fail both results, just like a failing process would
+	let encoder;
+	let error;
+
+	try {
+		encoder = new addon.Encoder(data, {
+			symbol_size: Number(options.symbol_size),
+			num_source_blocks: Number(options.num_source_blocks),
+			num_sub_blocks: Number(options.num_sub_blocks),
+			symbol_alignment: Number(options.symbol_alignment),
+		});
+	} catch (e) {
+		error = new Error(`RaptorQ encoding error: ${e.message}`);
+	}
+
+	const oti_promise = (error !== undefined
+		? Promise.reject(error)
+		: Promise.resolve(as_uint8_array(encoder.oti()))
+	);
+
+	const symbols = {
+		async *[Symbol.asyncIterator]() {
+			if (error !== undefined) {
+				throw error;
+			}
+
+			for (const packet of encoder.encoded_packets(Number(options.num_repair_symbols))) {
+				yield as_uint8_array(packet);
+			}
+		}
+	};
+
+	return {
+		oti: oti_promise,
+		encoding_packets: symbols
+	};
+};
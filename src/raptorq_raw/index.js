@@ -1,9 +1,13 @@
+import fs from "fs";
 import path from "path";
 import os from "os";
+import { createRequire } from "module";
 import { fileURLToPath } from "url";
 import { get_binary_path } from "../get_binary_path.js";
 import { encode } from "./encode.js";
 import { decode } from "./decode.js";
+import { encode_addon } from "./encode_addon.js";
+import { decode_addon } from "./decode_addon.js";
 import { oti_encode } from "./oti_encode.js";
 import { oti_decode } from "./oti_decode.js";
 
@@ -12,13 +16,19 @@ const __dirname = path.dirname(__filename);
 const root = path.join(__dirname, "..", "..");
 const binary_path = get_binary_path({ os, path }, root);
 
+// The Node-API addon is built alongside the binary and runs jobs in-process; where it has not been built, the binary is spawned instead
+const addon_path = path.join(path.dirname(binary_path), "raptorq.node");
+const addon = fs.existsSync(addon_path) ? createRequire(import.meta.url)(addon_path) : undefined;
+
 export const raptorq_raw = {
-	encode: (...args) => encode({
-		binary_path,
-	}, ...args),
-	decode: (...args) => decode({
-		binary_path,
-	}, ...args),
+	encode: (...args) => (addon !== undefined
+		? encode_addon({ addon }, ...args)
+		: encode({ binary_path }, ...args)
+	),
+	decode: (...args) => (addon !== undefined
+		? decode_addon({ addon }, ...args)
+		: decode({ binary_path }, ...args)
+	),
 	oti_encode,
 	oti_decode,
 };